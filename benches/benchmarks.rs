@@ -90,3 +90,44 @@ fn new_unresolved_and_resolve_separate(b: &mut test::Bencher) {
     }
     b.iter(the_function);
 }
+
+// Many addresses spread over a few libraries, like the samples a profiler
+// takes, to compare resolving them all at once with resolving them one by one.
+#[cfg(feature = "std")]
+fn sampled_ips() -> Vec<*mut std::ffi::c_void> {
+    let mut ips = Vec::new();
+    backtrace::trace(|frame| {
+        ips.push(frame.ip());
+        true
+    });
+    ips.iter()
+        .cycle()
+        .take(1024)
+        .enumerate()
+        .map(|(i, ip)| (*ip as usize).wrapping_sub(i % 16) as *mut std::ffi::c_void)
+        .collect()
+}
+
+#[bench]
+#[cfg(feature = "std")]
+fn resolve_each(b: &mut test::Bencher) {
+    let ips = sampled_ips();
+    b.iter(|| {
+        for ip in &ips {
+            backtrace::resolve(*ip, |symbol| {
+                test::black_box(symbol);
+            });
+        }
+    });
+}
+
+#[bench]
+#[cfg(feature = "std")]
+fn resolve_batch(b: &mut test::Bencher) {
+    let ips = sampled_ips();
+    b.iter(|| {
+        backtrace::resolve_batch(&ips, |i, symbol| {
+            test::black_box((i, symbol));
+        });
+    });
+}
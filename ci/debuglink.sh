@@ -26,14 +26,44 @@ mkdir -p `dirname $debugfile2`
 mv $debugfile1 $debugfile2
 $exefile $cratedir
 
+# A stale debug file in the same dir is rejected because of its CRC, falling
+# back to the one in the .debug subdir, or else to the stripped binary itself
+cp $debugfile2 $debugfile1
+echo stale >> $debugfile1
+$exefile $cratedir 2> target/debuglink.stderr
+grep -q DebuglinkCrcMismatch target/debuglink.stderr
+mv $debugfile2 $debugfile2.tmp
+! $exefile $cratedir
+mv $debugfile2.tmp $debugfile2
+rm $debugfile1 target/debuglink.stderr
+
+# Separate debug in a custom debug dir, given through the environment or
+# through `set_debug_dirs`
+customdir=`pwd`/target/debuglink-debug
+debugfile4="$customdir/$cratedir/target/debug/debuglink.debug"
+mkdir -p `dirname $debugfile4`
+mv $debugfile2 $debugfile4
+BACKTRACE_DEBUG_DIRS=$customdir $exefile $cratedir
+$exefile $cratedir $customdir
+! $exefile $cratedir
+
+# Separate debug in a custom debug dir's .build-id subdir
+id=`readelf -n $exefile | grep '^    Build ID: [0-9a-f]' | cut -b 15-`
+idfile4="$customdir/.build-id/${id:0:2}/${id:2}.debug"
+mkdir -p `dirname $idfile4`
+mv $debugfile4 $idfile4
+BACKTRACE_DEBUG_DIRS=/nonexistent:$customdir $exefile $cratedir
+$exefile $cratedir $customdir
+! $exefile $cratedir
+
 # Separate debug in /usr/lib/debug subdir
 debugfile3="/usr/lib/debug/$cratedir/target/debug/debuglink.debug"
 mkdir -p `dirname $debugfile3`
-mv $debugfile2 $debugfile3
+mv $idfile4 $debugfile3
+rm -r $customdir
 $exefile $cratedir
 
 # Separate debug in /usr/lib/debug/.build-id subdir
-id=`readelf -n $exefile | grep '^    Build ID: [0-9a-f]' | cut -b 15-`
 idfile="/usr/lib/debug/.build-id/${id:0:2}/${id:2}.debug"
 mkdir -p `dirname $idfile`
 mv $debugfile3 $idfile
@@ -0,0 +1,38 @@
+#!/bin/bash
+
+# MiniDebugInfo tests.
+# We build crates/minidebuginfo, strip it, and embed its function symbols as an
+# xz compressed ELF file in `.gnu_debugdata` like Fedora does, and test that it
+# can still find the symbols.
+
+set -ex
+
+targetdir=crates/minidebuginfo/target/debug
+exefile=$targetdir/minidebuginfo
+
+# Baseline; a full symbol table
+cargo clean --manifest-path crates/minidebuginfo/Cargo.toml
+cargo build --manifest-path crates/minidebuginfo/Cargo.toml
+$exefile
+
+# Keep only the function symbols which aren't in `.dynsym` already
+nm -D $exefile --format=posix --defined-only | awk '{ print $1 }' | sort > $targetdir/dynsyms
+nm $exefile --format=posix --defined-only \
+  | awk '{ if ($2 == "T" || $2 == "t") print $1 }' | sort > $targetdir/funcsyms
+comm -13 $targetdir/dynsyms $targetdir/funcsyms > $targetdir/keep_symbols
+objcopy --only-keep-debug $exefile $targetdir/mini_debuginfo
+objcopy -S --remove-section .gdb_index --remove-section .comment \
+  --keep-symbols=$targetdir/keep_symbols $targetdir/mini_debuginfo
+rm -f $targetdir/mini_debuginfo.xz
+xz $targetdir/mini_debuginfo
+
+# Stripped; the symbols are only in `.gnu_debugdata`
+strip --strip-all $exefile
+objcopy --add-section .gnu_debugdata=$targetdir/mini_debuginfo.xz $exefile
+$exefile
+
+# Stripped without `.gnu_debugdata` should fail
+objcopy --remove-section .gnu_debugdata $exefile
+! $exefile
+
+echo Success
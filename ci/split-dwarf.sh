@@ -0,0 +1,41 @@
+#!/bin/bash
+
+# Split DWARF tests.
+# We build crates/split_dwarf with its debuginfo split into `.dwo` files or a
+# `.dwp` package, and test that it can still find the debuginfo.
+
+set -ex
+
+cratedir=`pwd`/crates/split_dwarf
+targetdir=crates/split_dwarf/target/debug
+exefile=$targetdir/split_dwarf
+
+# Unpacked; `.dwo` files next to the object files
+cargo clean --manifest-path crates/split_dwarf/Cargo.toml
+CARGO_PROFILE_DEV_SPLIT_DEBUGINFO=unpacked \
+  cargo build --manifest-path crates/split_dwarf/Cargo.toml
+$exefile $cratedir
+
+# `.dwo` files moved next to the executable
+mkdir $targetdir/dwo
+mv $targetdir/deps/*.dwo $targetdir/dwo
+! $exefile $cratedir
+mv $targetdir/dwo/*.dwo $targetdir
+$exefile $cratedir
+
+# Missing `.dwo` files should fail
+rm $targetdir/*.dwo
+! $exefile $cratedir
+
+# Packed; a `.dwp` package next to the executable
+cargo clean --manifest-path crates/split_dwarf/Cargo.toml
+CARGO_PROFILE_DEV_SPLIT_DEBUGINFO=packed \
+  cargo build --manifest-path crates/split_dwarf/Cargo.toml
+$exefile $cratedir
+
+# Missing `.dwp` package should fail
+mv $exefile.dwp $exefile.dwp.tmp
+! $exefile $cratedir
+mv $exefile.dwp.tmp $exefile.dwp
+
+echo Success
@@ -8,6 +8,20 @@ fn main() {
     let crate_dir = std::env::args().skip(1).next().unwrap();
     let expect = std::path::Path::new(&crate_dir).join("src/main.rs");
 
+    // An optional second argument is an extra directory to search for the
+    // separate debuginfo.
+    if let Some(debug_dir) = std::env::args().nth(2) {
+        backtrace::set_debug_dirs(vec![debug_dir]);
+    }
+
+    // The hook is free to use the crate itself, which here means loading the
+    // debuginfo of this binary again while reporting that it was rejected.
+    backtrace::set_diagnostics_hook(Some(Box::new(|diagnostic| {
+        let bt = backtrace::Backtrace::new();
+        assert!(!bt.frames().is_empty());
+        eprintln!("diagnostic: {:?}", diagnostic);
+    })));
+
     let bt = backtrace::Backtrace::new();
     println!("{:?}", bt);
 
@@ -0,0 +1,17 @@
+// Test that the MiniDebugInfo is being used by checking that the backtrace
+// contains `main`, which isn't exported and so is only named in the symbol
+// table, even once that has been stripped and is only kept in
+// `.gnu_debugdata`.
+fn main() {
+    let bt = backtrace::Backtrace::new();
+    println!("{:?}", bt);
+
+    let found_main = bt.frames().iter().any(|frame| {
+        frame.symbols().iter().any(|symbol| match symbol.name() {
+            Some(name) => format!("{:#}", name) == "minidebuginfo::main",
+            None => false,
+        })
+    });
+
+    assert!(found_main);
+}
@@ -0,0 +1,43 @@
+// Test that the split debuginfo is being found, in `.dwo` files or in a `.dwp`
+// package, by checking that the backtrace contains `inlined`, which only the
+// split units know was inlined into `main`, and that the source filename uses
+// the path given in the command line arguments.
+#[inline(always)]
+fn inlined() -> backtrace::Backtrace {
+    backtrace::Backtrace::new()
+}
+
+fn main() {
+    let crate_dir = std::env::args().skip(1).next().unwrap();
+    let expect = std::path::Path::new(&crate_dir).join("src/main.rs");
+
+    let bt = inlined();
+    println!("{:?}", bt);
+
+    let mut found_inlined = false;
+    let mut found_main = false;
+
+    for frame in bt.frames() {
+        for symbol in frame.symbols() {
+            let name = match symbol.name() {
+                Some(name) => format!("{:#}", name),
+                None => continue,
+            };
+            if name == "split_dwarf::inlined" {
+                found_inlined = true;
+                assert!(symbol.is_inlined());
+                assert_eq!(symbol.filename().unwrap(), expect);
+                assert_eq!(symbol.call_filename().unwrap(), expect);
+            } else if name == "split_dwarf::main" {
+                found_main = true;
+                assert_eq!(symbol.filename().unwrap(), expect);
+            }
+        }
+        if found_main {
+            break;
+        }
+    }
+
+    assert!(found_inlined);
+    assert!(found_main);
+}
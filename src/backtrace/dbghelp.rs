@@ -49,6 +49,22 @@ impl Frame {
         Some(self.base_address)
     }
 
+    pub fn cfa(&self) -> Option<*mut c_void> {
+        None
+    }
+
+    pub fn register(&self, _n: u16) -> Option<usize> {
+        None
+    }
+
+    pub(crate) fn clone_with_registers(&self) -> Frame {
+        *self
+    }
+
+    pub fn is_return_address(&self) -> bool {
+        true
+    }
+
     fn addr_pc(&self) -> &ADDRESS64 {
         match self.stack_frame {
             StackFrame::New(ref new) => &new.AddrPC,
@@ -0,0 +1,303 @@
+//! Backtrace support by walking the chain of frame pointers.
+//!
+//! Every function compiled with frame pointers (`-C force-frame-pointers=yes`)
+//! stores a frame record on the stack holding the caller's frame pointer and
+//! the return address, and points the frame pointer register at it. Following
+//! that linked list is all that's needed to produce a backtrace, which makes
+//! this much cheaper than unwinding through CFI and usable without any system
+//! unwinder or `std` at all.
+//!
+//! Frame records are only ever read from inside the bounds of the current
+//! stack, so a corrupt chain (for example through code compiled without frame
+//! pointers, which uses the register for something else) ends the backtrace
+//! early instead of faulting. The bounds come from the hook installed with
+//! `set_stack_limits_hook`, falling back to asking pthreads on Linux and
+//! Android. If neither knows where the stack is, nothing is walked at all.
+//!
+//! This is only used when the `frame-pointers` feature is enabled, on x86,
+//! x86_64 and aarch64.
+
+use core::ffi::c_void;
+use core::mem;
+use core::ops::Range;
+
+#[derive(Clone)]
+pub struct Frame {
+    ip: *mut c_void,
+    sp: *mut c_void,
+    symbol_address: *mut c_void,
+    is_return_address: bool,
+}
+
+// The frame only contains plain addresses, nothing in it points at the state
+// of the walk.
+unsafe impl Send for Frame {}
+unsafe impl Sync for Frame {}
+
+impl Frame {
+    pub fn ip(&self) -> *mut c_void {
+        self.ip
+    }
+
+    pub fn sp(&self) -> *mut c_void {
+        self.sp
+    }
+
+    pub fn symbol_address(&self) -> *mut c_void {
+        self.symbol_address
+    }
+
+    pub fn module_base_address(&self) -> Option<*mut c_void> {
+        None
+    }
+
+    pub fn cfa(&self) -> Option<*mut c_void> {
+        None
+    }
+
+    pub fn register(&self, _n: u16) -> Option<usize> {
+        None
+    }
+
+    pub(crate) fn clone_with_registers(&self) -> Frame {
+        self.clone()
+    }
+
+    pub fn is_return_address(&self) -> bool {
+        self.is_return_address
+    }
+
+    // Only used for frames found by unwinding from a signal context, which
+    // isn't supported everywhere.
+    #[allow(dead_code)]
+    pub(crate) fn from_addresses(
+        ip: *mut c_void,
+        sp: *mut c_void,
+        symbol_address: *mut c_void,
+        is_return_address: bool,
+        _cfa: Option<*mut c_void>,
+        _registers: [Option<usize>; super::PRESERVED_REGISTERS.len()],
+    ) -> Frame {
+        Frame {
+            ip,
+            sp,
+            symbol_address,
+            is_return_address,
+        }
+    }
+}
+
+#[inline(never)]
+pub unsafe fn trace(cb: &mut dyn FnMut(&super::Frame) -> bool) {
+    let (fp, sp) = arch::registers();
+    let limits = match stack_limits() {
+        Some(limits) if limits.start <= sp && sp < limits.end => limits,
+        _ => return,
+    };
+    walk(fp, sp..limits.end, &mut |frame| {
+        cb(&super::Frame { inner: frame })
+    });
+}
+
+/// Follows the chain of frame records starting at `fp`, only ever reading
+/// memory inside of `limits`.
+///
+/// Each record must be aligned and lie strictly above the previous one, which
+/// guarantees that the walk terminates.
+unsafe fn walk(mut fp: usize, limits: Range<usize>, cb: &mut dyn FnMut(Frame) -> bool) {
+    const WORD: usize = mem::size_of::<usize>();
+
+    let mut low = limits.start;
+    loop {
+        let record_end = match fp.checked_add(2 * WORD) {
+            Some(end) => end,
+            None => break,
+        };
+        if fp < low || fp % WORD != 0 || record_end > limits.end {
+            break;
+        }
+        let next = *(fp as *const usize);
+        let ip = *((fp + WORD) as *const usize);
+        if ip == 0 {
+            break;
+        }
+        // Frame records don't say anything about the function they belong to,
+        // so the ip is the best guess for the symbol address.
+        let frame = Frame {
+            ip: ip as *mut c_void,
+            sp: record_end as *mut c_void,
+            symbol_address: ip as *mut c_void,
+            is_return_address: true,
+        };
+        if !cb(frame) {
+            break;
+        }
+        low = record_end;
+        fp = next;
+    }
+}
+
+/// Returns the bounds of the stack the current thread is running on.
+fn stack_limits() -> Option<Range<usize>> {
+    if let Some(hook) = super::stack_limits_hook() {
+        if let Some(limits) = hook() {
+            return Some(limits);
+        }
+    }
+    os_stack_limits()
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn os_stack_limits() -> Option<Range<usize>> {
+    unsafe {
+        let mut attr: libc::pthread_attr_t = mem::zeroed();
+        if libc::pthread_getattr_np(libc::pthread_self(), &mut attr) != 0 {
+            return None;
+        }
+        let mut addr = 0 as *mut libc::c_void;
+        let mut size = 0;
+        let ret = libc::pthread_attr_getstack(&attr, &mut addr, &mut size);
+        libc::pthread_attr_destroy(&mut attr);
+        if ret != 0 {
+            return None;
+        }
+        let start = addr as usize;
+        Some(start..start + size)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn os_stack_limits() -> Option<Range<usize>> {
+    None
+}
+
+#[cfg(target_arch = "x86_64")]
+mod arch {
+    /// Returns the frame pointer and stack pointer of the function this is
+    /// inlined into.
+    #[inline(always)]
+    pub unsafe fn registers() -> (usize, usize) {
+        let (fp, sp): (usize, usize);
+        core::arch::asm!(
+            "mov {fp}, rbp",
+            "mov {sp}, rsp",
+            fp = out(reg) fp,
+            sp = out(reg) sp,
+            options(nomem, nostack, preserves_flags),
+        );
+        (fp, sp)
+    }
+}
+
+#[cfg(target_arch = "x86")]
+mod arch {
+    /// Returns the frame pointer and stack pointer of the function this is
+    /// inlined into.
+    #[inline(always)]
+    pub unsafe fn registers() -> (usize, usize) {
+        let (fp, sp): (usize, usize);
+        core::arch::asm!(
+            "mov {fp}, ebp",
+            "mov {sp}, esp",
+            fp = out(reg) fp,
+            sp = out(reg) sp,
+            options(nomem, nostack, preserves_flags),
+        );
+        (fp, sp)
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod arch {
+    /// Returns the frame pointer and stack pointer of the function this is
+    /// inlined into.
+    #[inline(always)]
+    pub unsafe fn registers() -> (usize, usize) {
+        let (fp, sp): (usize, usize);
+        core::arch::asm!(
+            "mov {fp}, x29",
+            "mov {sp}, sp",
+            fp = out(reg) fp,
+            sp = out(reg) sp,
+            options(nomem, nostack, preserves_flags),
+        );
+        (fp, sp)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    const WORD: usize = mem::size_of::<usize>();
+
+    fn walk_stack(stack: &[usize], fp: usize) -> Vec<usize> {
+        let base = stack.as_ptr() as usize;
+        let mut ips = Vec::new();
+        unsafe {
+            walk(
+                base + fp * WORD,
+                base..base + stack.len() * WORD,
+                &mut |frame| {
+                    ips.push(frame.ip() as usize);
+                    true
+                },
+            );
+        }
+        ips
+    }
+
+    #[test]
+    fn follows_chain() {
+        let mut stack = [0usize; 8];
+        let base = stack.as_ptr() as usize;
+        stack[0] = base + 2 * WORD;
+        stack[1] = 0x1000;
+        stack[2] = base + 6 * WORD;
+        stack[3] = 0x2000;
+        stack[6] = 0;
+        stack[7] = 0x3000;
+        assert_eq!(walk_stack(&stack, 0), [0x1000, 0x2000, 0x3000]);
+    }
+
+    #[test]
+    fn stops_at_pointer_outside_stack() {
+        let mut stack = [0usize; 4];
+        let base = stack.as_ptr() as usize;
+        stack[0] = base + 2 * WORD;
+        stack[1] = 0x1000;
+        stack[2] = 0xdead_0000;
+        stack[3] = 0x2000;
+        assert_eq!(walk_stack(&stack, 0), [0x1000, 0x2000]);
+    }
+
+    #[test]
+    fn stops_at_loop() {
+        let mut stack = [0usize; 4];
+        let base = stack.as_ptr() as usize;
+        stack[0] = base + 2 * WORD;
+        stack[1] = 0x1000;
+        stack[2] = base;
+        stack[3] = 0x2000;
+        assert_eq!(walk_stack(&stack, 0), [0x1000, 0x2000]);
+    }
+
+    #[test]
+    fn stops_at_misaligned_pointer() {
+        let mut stack = [0usize; 4];
+        let base = stack.as_ptr() as usize;
+        stack[0] = base + 2 * WORD + 1;
+        stack[1] = 0x1000;
+        assert_eq!(walk_stack(&stack, 0), [0x1000]);
+    }
+
+    #[test]
+    fn stops_at_record_crossing_limit() {
+        let mut stack = [0usize; 4];
+        let base = stack.as_ptr() as usize;
+        stack[0] = base + 3 * WORD;
+        stack[1] = 0x1000;
+        assert_eq!(walk_stack(&stack, 0), [0x1000]);
+    }
+}
@@ -0,0 +1,677 @@
+//! Backtrace support using a pure-Rust unwinder built on the `gimli` crate.
+//!
+//! This walks the stack using the DWARF call frame information that every
+//! loaded object carries in its `.eh_frame` section (located through the
+//! `.eh_frame_hdr` lookup table and `dl_iterate_phdr`), the same information
+//! libgcc_s and LLVM's libunwind use. No system unwinder is involved at all,
+//! which makes this useful for statically linked binaries and for sandboxes
+//! where the system unwinder is missing or broken.
+//!
+//! The unwinder never allocates: all unwinding state, including the CFI
+//! evaluation tables and DWARF expression stacks, lives on the stack.
+//!
+//! This is used as the backend of `trace` when the `gimli-unwind` feature is
+//! enabled, and only on ELF platforms with `dl_iterate_phdr` on x86_64 and
+//! aarch64. Independently of that it's also how `trace_from_context` walks the
+//! stack of an interrupted context on Linux, since unlike the system unwinder
+//! it can start from an arbitrary set of registers.
+//!
+//! Memory and unwind information are only ever looked up through an
+//! `AddressSpace`, which with the `ptrace` and `coredump` features is another
+//! process.
+
+use super::PRESERVED_REGISTERS;
+use addr2line::gimli::{
+    self, BaseAddresses, CfaRule, EhFrame, EhFrameHdr, EndianSlice, Evaluation, EvaluationResult,
+    EvaluationStorage, Expression, Location, NativeEndian, Register, RegisterRule, UnwindContext,
+    UnwindContextStorage, UnwindSection, Value,
+};
+use core::ffi::c_void;
+use core::{mem, slice};
+
+#[derive(Clone)]
+pub struct Frame {
+    ip: *mut c_void,
+    sp: *mut c_void,
+    symbol_address: *mut c_void,
+    is_return_address: bool,
+    cfa: Option<*mut c_void>,
+    // The values of `PRESERVED_REGISTERS`, where they were known.
+    registers: [Option<usize>; PRESERVED_REGISTERS.len()],
+}
+
+// The frame only contains plain addresses, nothing in it points at the state
+// of the unwinder.
+unsafe impl Send for Frame {}
+unsafe impl Sync for Frame {}
+
+// Only used as is when this is the backend of `trace`, otherwise frames are
+// converted to the other backend's frames first.
+#[allow(dead_code)]
+impl Frame {
+    pub fn ip(&self) -> *mut c_void {
+        self.ip
+    }
+
+    pub fn sp(&self) -> *mut c_void {
+        self.sp
+    }
+
+    pub fn symbol_address(&self) -> *mut c_void {
+        self.symbol_address
+    }
+
+    pub fn module_base_address(&self) -> Option<*mut c_void> {
+        None
+    }
+
+    pub fn cfa(&self) -> Option<*mut c_void> {
+        self.cfa
+    }
+
+    pub fn register(&self, n: u16) -> Option<usize> {
+        let index = PRESERVED_REGISTERS.iter().position(|&r| r == n)?;
+        self.registers[index]
+    }
+
+    pub fn is_return_address(&self) -> bool {
+        self.is_return_address
+    }
+
+    pub(crate) fn registers(&self) -> [Option<usize>; PRESERVED_REGISTERS.len()] {
+        self.registers
+    }
+
+    pub(crate) fn from_addresses(
+        ip: *mut c_void,
+        sp: *mut c_void,
+        symbol_address: *mut c_void,
+        is_return_address: bool,
+        cfa: Option<*mut c_void>,
+        registers: [Option<usize>; PRESERVED_REGISTERS.len()],
+    ) -> Frame {
+        Frame {
+            ip,
+            sp,
+            symbol_address,
+            is_return_address,
+            cfa,
+            registers,
+        }
+    }
+
+    pub(crate) fn clone_with_registers(&self) -> Frame {
+        self.clone()
+    }
+}
+
+// Capturing the current registers takes inline assembly, which is newer than
+// the minimum supported Rust version, so this is only built when asked for.
+// It's still unused if the `frame-pointers` backend was picked over this one.
+#[cfg(feature = "gimli-unwind")]
+#[allow(dead_code)]
+#[inline(never)]
+pub unsafe fn trace(cb: &mut dyn FnMut(&super::Frame) -> bool) {
+    let mut regs = Registers::new();
+    arch::capture(&mut regs);
+    walk(Cursor::new(&Local, regs), cb);
+}
+
+/// Walks the stack of the context interrupted by a signal, starting with the
+/// interrupted frame itself.
+///
+/// `context` should point to the `ucontext_t` passed to a `SA_SIGINFO` signal
+/// handler.
+#[cfg(target_os = "linux")]
+pub unsafe fn trace_from_context(
+    context: *const c_void,
+    cb: &mut dyn FnMut(&super::Frame) -> bool,
+) {
+    let mut regs = Registers::new();
+    arch::load_context(&mut regs, &*(context as *const libc::ucontext_t));
+    walk(Cursor::new(&Local, regs), cb);
+}
+
+unsafe fn walk(mut cursor: Cursor<'_, Local>, cb: &mut dyn FnMut(&super::Frame) -> bool) {
+    while let Some(frame) = cursor.next() {
+        // Convert to whatever frames look like for the backend of `trace`,
+        // which doesn't have to be this one.
+        let cx = super::Frame {
+            inner: super::FrameImp::from_addresses(
+                frame.ip,
+                frame.sp,
+                frame.symbol_address,
+                frame.is_return_address,
+                frame.cfa,
+                frame.registers,
+            ),
+        };
+        if !cb(&cx) {
+            break;
+        }
+    }
+}
+
+/// The register state of one frame, indexed by DWARF register number.
+#[derive(Clone)]
+pub(crate) struct Registers {
+    values: [usize; MAX_REGISTERS],
+    known: u64,
+    pc: usize,
+}
+
+const MAX_REGISTERS: usize = 32;
+
+impl Registers {
+    pub(crate) fn new() -> Registers {
+        Registers {
+            values: [0; MAX_REGISTERS],
+            known: 0,
+            pc: 0,
+        }
+    }
+
+    /// Returns the registers of a thread stopped by `ptrace`, as read through
+    /// `PTRACE_GETREGSET`, or as saved in the `NT_PRSTATUS` note of a core
+    /// file.
+    #[cfg(any(feature = "ptrace", feature = "coredump"))]
+    pub(crate) fn from_user_regs(user: &libc::user_regs_struct) -> Registers {
+        let mut regs = Registers::new();
+        arch::load_user_regs(&mut regs, user);
+        regs
+    }
+
+    pub(crate) fn get(&self, register: u16) -> Option<usize> {
+        let register = register as usize;
+        if register < MAX_REGISTERS && self.known & (1 << register) != 0 {
+            Some(self.values[register])
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn set(&mut self, register: u16, value: usize) {
+        let register = register as usize;
+        if register < MAX_REGISTERS {
+            self.values[register] = value;
+            self.known |= 1 << register;
+        }
+    }
+
+    fn forget(&mut self, register: u16) {
+        let register = register as usize;
+        if register < MAX_REGISTERS {
+            self.known &= !(1 << register);
+        }
+    }
+
+    pub(crate) fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub(crate) fn set_pc(&mut self, pc: usize) {
+        self.pc = pc;
+    }
+
+    pub(crate) fn sp(&self) -> usize {
+        self.get(arch::SP).unwrap_or(0)
+    }
+}
+
+/// Walks the stack one frame at a time, starting from a set of registers.
+pub(crate) struct Cursor<'a, S> {
+    space: &'a S,
+    regs: Registers,
+    // Whether `regs.pc` is the exact address of an instruction rather than a
+    // return address, which is the case for the first frame and for frames
+    // interrupted by a signal.
+    exact: bool,
+    done: bool,
+}
+
+impl<'a, S: AddressSpace> Cursor<'a, S> {
+    pub(crate) fn new(space: &'a S, regs: Registers) -> Cursor<'a, S> {
+        Cursor {
+            space,
+            regs,
+            exact: true,
+            done: false,
+        }
+    }
+
+    /// Returns the current frame and moves the cursor on to its caller.
+    pub(crate) unsafe fn next(&mut self) -> Option<Frame> {
+        if self.done || self.regs.pc() == 0 {
+            return None;
+        }
+        let pc = self.regs.pc();
+        let lookup = if self.exact { pc } else { pc - 1 };
+        let mut frame = Frame {
+            ip: pc as *mut c_void,
+            sp: self.regs.sp() as *mut c_void,
+            symbol_address: 0 as *mut c_void,
+            is_return_address: !self.exact,
+            cfa: None,
+            registers: [None; PRESERVED_REGISTERS.len()],
+        };
+        for (value, &n) in frame.registers.iter_mut().zip(PRESERVED_REGISTERS) {
+            *value = self.regs.get(n);
+        }
+        match step(self.space, &self.regs, lookup) {
+            Some(step) => {
+                frame.symbol_address = step.function as *mut c_void;
+                frame.cfa = Some(step.cfa as *mut c_void);
+                // Guard against loops in a corrupt stack by requiring that the
+                // stack grows towards its base, except when leaving a signal
+                // handler which may be running on an alternate stack.
+                if !step.signal_frame && step.regs.sp() <= self.regs.sp() {
+                    self.done = true;
+                }
+                self.exact = step.signal_frame;
+                self.regs = step.regs;
+            }
+            None => self.done = true,
+        }
+        Some(frame)
+    }
+}
+
+/// The memory of the process being unwound, along with the unwind information
+/// of the code loaded into it.
+pub(crate) trait AddressSpace {
+    /// Reads the `size` byte integer at `addr`.
+    unsafe fn read(&self, addr: usize, size: u8) -> Option<usize>;
+
+    /// Returns the unwind information of the object containing `pc`.
+    unsafe fn find_object(&self, pc: usize) -> Option<Object<'_>>;
+}
+
+/// The unwind information of an object, as loaded into the address space it's
+/// being unwound in.
+pub(crate) struct Object<'a> {
+    pub(crate) eh_frame_hdr: &'a [u8],
+    pub(crate) eh_frame_hdr_address: usize,
+    /// The contents of `.eh_frame`, possibly followed by whatever comes after
+    /// it, since the header doesn't say how long it is.
+    pub(crate) eh_frame: &'a [u8],
+    pub(crate) eh_frame_address: usize,
+}
+
+struct Step {
+    regs: Registers,
+    cfa: usize,
+    function: usize,
+    signal_frame: bool,
+}
+
+struct StoreOnStack;
+
+impl<R: gimli::Reader> UnwindContextStorage<R> for StoreOnStack {
+    type Rules = [(Register, RegisterRule<R>); 32];
+    type Stack = [gimli::UnwindTableRow<R, Self>; 4];
+}
+
+impl<R: gimli::Reader> EvaluationStorage<R> for StoreOnStack {
+    type Stack = [Value; 64];
+    type ExpressionStack = [(R, R); 4];
+    type Result = [gimli::Piece<R>; 1];
+}
+
+/// Computes the registers of the caller of the frame described by `regs`,
+/// using the unwind information that covers `lookup`.
+unsafe fn step<S: AddressSpace>(space: &S, regs: &Registers, lookup: usize) -> Option<Step> {
+    let object = space.find_object(lookup)?;
+    let bases = BaseAddresses::default()
+        .set_eh_frame_hdr(object.eh_frame_hdr_address as u64)
+        .set_eh_frame(object.eh_frame_address as u64);
+    let hdr = EhFrameHdr::new(object.eh_frame_hdr, NativeEndian)
+        .parse(&bases, mem::size_of::<usize>() as u8)
+        .ok()?;
+    let mut eh_frame = EhFrame::new(object.eh_frame, NativeEndian);
+    eh_frame.set_address_size(mem::size_of::<usize>() as u8);
+    let fde = hdr
+        .table()?
+        .fde_for_address(&eh_frame, &bases, lookup as u64, EhFrame::cie_from_offset)
+        .ok()?;
+    let mut ctx = UnwindContext::<_, StoreOnStack>::new_in();
+    let row = fde
+        .unwind_info_for_address(&eh_frame, &bases, &mut ctx, lookup as u64)
+        .ok()?;
+    let encoding = fde.cie().encoding();
+
+    let cfa = match *row.cfa() {
+        CfaRule::RegisterAndOffset { register, offset } => {
+            (regs.get(register.0)? as i64).wrapping_add(offset) as usize
+        }
+        CfaRule::Expression(ref expr) => evaluate(space, regs, expr, encoding, None)?,
+    };
+
+    // Registers without a rule keep their value, which is what the compilers
+    // emitting `.eh_frame` expect for callee-saved registers.
+    let mut caller = regs.clone();
+    for &(register, ref rule) in row.registers() {
+        let value = match *rule {
+            RegisterRule::Undefined => None,
+            RegisterRule::SameValue => regs.get(register.0),
+            RegisterRule::Offset(offset) => Some(space.read(cfa.wrapping_add(offset as usize), 8)?),
+            RegisterRule::ValOffset(offset) => Some(cfa.wrapping_add(offset as usize)),
+            RegisterRule::Register(other) => regs.get(other.0),
+            RegisterRule::Expression(ref expr) => {
+                let addr = evaluate(space, regs, expr, encoding, Some(cfa))?;
+                Some(space.read(addr, 8)?)
+            }
+            RegisterRule::ValExpression(ref expr) => {
+                Some(evaluate(space, regs, expr, encoding, Some(cfa))?)
+            }
+            RegisterRule::Architectural => return None,
+        };
+        match value {
+            Some(value) => caller.set(register.0, value),
+            None => caller.forget(register.0),
+        }
+    }
+    // gimli leaves undefined registers out of `registers()`, but an undefined
+    // return address is how the outermost frame (e.g. `_start`) marks the end
+    // of the stack, so it must not inherit the callee's value.
+    let ra = fde.cie().return_address_register();
+    if let RegisterRule::Undefined = row.register(ra) {
+        caller.forget(ra.0);
+    }
+    caller.set(arch::SP, cfa);
+    caller.set_pc(caller.get(ra.0).unwrap_or(0));
+
+    Some(Step {
+        regs: caller,
+        cfa,
+        function: fde.initial_address() as usize,
+        signal_frame: fde.is_signal_trampoline(),
+    })
+}
+
+/// Evaluates a DWARF expression from a CFI rule and returns the value or
+/// address it computes.
+///
+/// Expressions for register rules start with the CFA pushed on the stack.
+unsafe fn evaluate<S: AddressSpace>(
+    space: &S,
+    regs: &Registers,
+    expr: &Expression<EndianSlice<'_, NativeEndian>>,
+    encoding: gimli::Encoding,
+    cfa: Option<usize>,
+) -> Option<usize> {
+    let mut eval = Evaluation::<_, StoreOnStack>::new_in(expr.0, encoding);
+    if let Some(cfa) = cfa {
+        eval.set_initial_value(cfa as u64);
+    }
+    let mut result = eval.evaluate().ok()?;
+    loop {
+        result = match result {
+            EvaluationResult::Complete => break,
+            EvaluationResult::RequiresMemory { address, size, .. } => {
+                let value = space.read(address as usize, size)?;
+                eval.resume_with_memory(Value::Generic(value as u64)).ok()?
+            }
+            EvaluationResult::RequiresRegister { register, .. } => {
+                let value = regs.get(register.0)?;
+                eval.resume_with_register(Value::Generic(value as u64))
+                    .ok()?
+            }
+            _ => return None,
+        };
+    }
+    match eval.as_result() {
+        [piece] => match piece.location {
+            Location::Address { address } => Some(address as usize),
+            Location::Value {
+                value: Value::Generic(value),
+            } => Some(value as usize),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the address of `.eh_frame` as recorded in the `.eh_frame_hdr`
+/// loaded at `address`.
+pub(crate) fn eh_frame_address(eh_frame_hdr: &[u8], address: usize) -> Option<usize> {
+    let bases = BaseAddresses::default().set_eh_frame_hdr(address as u64);
+    let hdr = EhFrameHdr::new(eh_frame_hdr, NativeEndian)
+        .parse(&bases, mem::size_of::<usize>() as u8)
+        .ok()?;
+    match hdr.eh_frame_ptr() {
+        gimli::Pointer::Direct(addr) => Some(addr as usize),
+        gimli::Pointer::Indirect(_) => None,
+    }
+}
+
+/// The address space of the current process, where objects are found through
+/// `dl_iterate_phdr`.
+pub(crate) struct Local;
+
+impl AddressSpace for Local {
+    unsafe fn read(&self, addr: usize, size: u8) -> Option<usize> {
+        if addr == 0 {
+            return None;
+        }
+        Some(match size {
+            1 => (addr as *const u8).read_unaligned() as usize,
+            2 => (addr as *const u16).read_unaligned() as usize,
+            4 => (addr as *const u32).read_unaligned() as usize,
+            8 => (addr as *const u64).read_unaligned() as usize,
+            _ => return None,
+        })
+    }
+
+    unsafe fn find_object(&self, pc: usize) -> Option<Object<'_>> {
+        let mut search = Search { pc, found: None };
+        libc::dl_iterate_phdr(Some(callback), &mut search as *mut Search as *mut _);
+        let found = search.found?;
+        let eh_frame_hdr =
+            slice::from_raw_parts(found.eh_frame_hdr as *const u8, found.eh_frame_hdr_len);
+        let eh_frame = eh_frame_address(eh_frame_hdr, found.eh_frame_hdr)?;
+        if eh_frame < found.segment_start || eh_frame >= found.segment_end {
+            return None;
+        }
+        Some(Object {
+            eh_frame_hdr,
+            eh_frame_hdr_address: found.eh_frame_hdr,
+            eh_frame: slice::from_raw_parts(eh_frame as *const u8, found.segment_end - eh_frame),
+            eh_frame_address: eh_frame,
+        })
+    }
+}
+
+/// Where the unwind information of a loaded object is.
+struct Loaded {
+    eh_frame_hdr: usize,
+    eh_frame_hdr_len: usize,
+    // The bounds of the `PT_LOAD` segment containing `.eh_frame_hdr`, which
+    // `.eh_frame` is always placed next to.
+    segment_start: usize,
+    segment_end: usize,
+}
+
+struct Search {
+    pc: usize,
+    found: Option<Loaded>,
+}
+
+// `info` should be a valid pointers.
+// `data` should be a valid pointer to a `Search`.
+unsafe extern "C" fn callback(
+    info: *mut libc::dl_phdr_info,
+    _size: libc::size_t,
+    data: *mut libc::c_void,
+) -> libc::c_int {
+    let info = &*info;
+    let search = &mut *(data as *mut Search);
+    let phdrs = if info.dlpi_phdr.is_null() {
+        &[]
+    } else {
+        slice::from_raw_parts(info.dlpi_phdr, info.dlpi_phnum as usize)
+    };
+    let bias = info.dlpi_addr as usize;
+    let segment = |addr: usize| {
+        phdrs
+            .iter()
+            .filter(|phdr| phdr.p_type == object::elf::PT_LOAD)
+            .map(|phdr| {
+                let start = bias.wrapping_add(phdr.p_vaddr as usize);
+                (start, start.wrapping_add(phdr.p_memsz as usize))
+            })
+            .find(|&(start, end)| start <= addr && addr < end)
+    };
+    if segment(search.pc).is_none() {
+        return 0;
+    }
+    let hdr = phdrs
+        .iter()
+        .find(|phdr| phdr.p_type == object::elf::PT_GNU_EH_FRAME);
+    if let Some(hdr) = hdr {
+        let eh_frame_hdr = bias.wrapping_add(hdr.p_vaddr as usize);
+        if let Some((segment_start, segment_end)) = segment(eh_frame_hdr) {
+            search.found = Some(Loaded {
+                eh_frame_hdr,
+                eh_frame_hdr_len: hdr.p_memsz as usize,
+                segment_start,
+                segment_end,
+            });
+        }
+    }
+    1
+}
+
+#[cfg(target_arch = "x86_64")]
+mod arch {
+    use super::Registers;
+
+    pub const SP: u16 = 7;
+
+    /// Records the callee-saved registers, the stack pointer and the program
+    /// counter at the point this is inlined into.
+    #[cfg(feature = "gimli-unwind")]
+    #[inline(always)]
+    pub unsafe fn capture(regs: &mut Registers) {
+        let mut values = [0usize; 8];
+        core::arch::asm!(
+            "mov [{p}], rbx",
+            "mov [{p} + 8], rbp",
+            "mov [{p} + 16], rsp",
+            "mov [{p} + 24], r12",
+            "mov [{p} + 32], r13",
+            "mov [{p} + 40], r14",
+            "mov [{p} + 48], r15",
+            "lea {t}, [rip]",
+            "mov [{p} + 56], {t}",
+            p = in(reg) values.as_mut_ptr(),
+            t = out(reg) _,
+            options(nostack, preserves_flags),
+        );
+        for (&register, &value) in [3, 6, 7, 12, 13, 14, 15].iter().zip(values.iter()) {
+            regs.set(register, value);
+        }
+        regs.set_pc(values[7]);
+    }
+
+    /// Records all general purpose registers and the program counter saved in
+    /// a signal context.
+    #[cfg(target_os = "linux")]
+    pub unsafe fn load_context(regs: &mut Registers, context: &libc::ucontext_t) {
+        // The DWARF register numbers of `rax` through `r15` next to where
+        // they're saved in `gregs`.
+        const GREGS: [(u16, libc::c_int); 16] = [
+            (0, libc::REG_RAX),
+            (1, libc::REG_RDX),
+            (2, libc::REG_RCX),
+            (3, libc::REG_RBX),
+            (4, libc::REG_RSI),
+            (5, libc::REG_RDI),
+            (6, libc::REG_RBP),
+            (7, libc::REG_RSP),
+            (8, libc::REG_R8),
+            (9, libc::REG_R9),
+            (10, libc::REG_R10),
+            (11, libc::REG_R11),
+            (12, libc::REG_R12),
+            (13, libc::REG_R13),
+            (14, libc::REG_R14),
+            (15, libc::REG_R15),
+        ];
+        let gregs = &context.uc_mcontext.gregs;
+        for &(register, index) in GREGS.iter() {
+            regs.set(register, gregs[index as usize] as usize);
+        }
+        regs.set_pc(gregs[libc::REG_RIP as usize] as usize);
+    }
+
+    /// Records all general purpose registers and the program counter of a
+    /// thread stopped by `ptrace`.
+    #[cfg(any(feature = "ptrace", feature = "coredump"))]
+    pub fn load_user_regs(regs: &mut Registers, user: &libc::user_regs_struct) {
+        let values = [
+            user.rax, user.rdx, user.rcx, user.rbx, user.rsi, user.rdi, user.rbp, user.rsp,
+            user.r8, user.r9, user.r10, user.r11, user.r12, user.r13, user.r14, user.r15,
+        ];
+        for (register, &value) in (0..16).zip(values.iter()) {
+            regs.set(register, value as usize);
+        }
+        regs.set_pc(user.rip as usize);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod arch {
+    use super::Registers;
+
+    pub const SP: u16 = 31;
+
+    /// Records the callee-saved registers, the link register, the stack
+    /// pointer and the program counter at the point this is inlined into.
+    #[cfg(feature = "gimli-unwind")]
+    #[inline(always)]
+    pub unsafe fn capture(regs: &mut Registers) {
+        let mut values = [0usize; 14];
+        core::arch::asm!(
+            "stp x19, x20, [{p}]",
+            "stp x21, x22, [{p}, #16]",
+            "stp x23, x24, [{p}, #32]",
+            "stp x25, x26, [{p}, #48]",
+            "stp x27, x28, [{p}, #64]",
+            "stp x29, x30, [{p}, #80]",
+            "mov {t}, sp",
+            "str {t}, [{p}, #96]",
+            "adr {t}, .",
+            "str {t}, [{p}, #104]",
+            p = in(reg) values.as_mut_ptr(),
+            t = out(reg) _,
+            options(nostack, preserves_flags),
+        );
+        for (register, &value) in (19..=31).zip(values.iter()) {
+            regs.set(register, value);
+        }
+        regs.set_pc(values[13]);
+    }
+
+    /// Records all general purpose registers, the stack pointer and the
+    /// program counter saved in a signal context.
+    #[cfg(target_os = "linux")]
+    pub unsafe fn load_context(regs: &mut Registers, context: &libc::ucontext_t) {
+        let mcontext = &context.uc_mcontext;
+        for (register, &value) in (0..31).zip(mcontext.regs.iter()) {
+            regs.set(register, value as usize);
+        }
+        regs.set(SP, mcontext.sp as usize);
+        regs.set_pc(mcontext.pc as usize);
+    }
+
+    /// Records all general purpose registers, the stack pointer and the
+    /// program counter of a thread stopped by `ptrace`.
+    #[cfg(any(feature = "ptrace", feature = "coredump"))]
+    pub fn load_user_regs(regs: &mut Registers, user: &libc::user_regs_struct) {
+        for (register, &value) in (0..31).zip(user.regs.iter()) {
+            regs.set(register, value as usize);
+        }
+        regs.set(SP, user.sp as usize);
+        regs.set_pc(user.pc as usize);
+    }
+}
@@ -16,6 +16,7 @@
 //! This is the default unwinding API for all non-Windows platforms currently.
 
 use super::super::Bomb;
+use super::PRESERVED_REGISTERS;
 use core::ffi::c_void;
 
 pub enum Frame {
@@ -24,6 +25,10 @@ pub enum Frame {
         ip: *mut c_void,
         sp: *mut c_void,
         symbol_address: *mut c_void,
+        cfa: Option<*mut c_void>,
+        // The values of `PRESERVED_REGISTERS`, where they were known.
+        registers: [Option<usize>; PRESERVED_REGISTERS.len()],
+        is_return_address: bool,
     },
 }
 
@@ -76,14 +81,85 @@ impl Frame {
     pub fn module_base_address(&self) -> Option<*mut c_void> {
         None
     }
+
+    pub fn cfa(&self) -> Option<*mut c_void> {
+        match *self {
+            Frame::Raw(ctx) => unsafe { uw::get_cfa(ctx).map(|cfa| cfa as *mut c_void) },
+            Frame::Cloned { cfa, .. } => cfa,
+        }
+    }
+
+    pub fn register(&self, n: u16) -> Option<usize> {
+        let index = PRESERVED_REGISTERS.iter().position(|&r| r == n)?;
+        match *self {
+            Frame::Raw(ctx) => Some(unsafe { uw::_Unwind_GetGR(ctx, n as libc::c_int) as usize }),
+            Frame::Cloned { registers, .. } => registers[index],
+        }
+    }
+
+    pub fn is_return_address(&self) -> bool {
+        match *self {
+            // The unwinder tells apart frames interrupted by a signal, whose ip
+            // is the instruction that was about to be executed.
+            Frame::Raw(ctx) => unsafe {
+                let mut ip_before_insn = 0;
+                uw::_Unwind_GetIPInfo(ctx, &mut ip_before_insn);
+                ip_before_insn == 0
+            },
+            Frame::Cloned {
+                is_return_address, ..
+            } => is_return_address,
+        }
+    }
+
+    // Only used for frames found by unwinding from a signal context, which
+    // isn't supported everywhere.
+    #[allow(dead_code)]
+    pub(crate) fn from_addresses(
+        ip: *mut c_void,
+        sp: *mut c_void,
+        symbol_address: *mut c_void,
+        is_return_address: bool,
+        cfa: Option<*mut c_void>,
+        registers: [Option<usize>; PRESERVED_REGISTERS.len()],
+    ) -> Frame {
+        Frame::Cloned {
+            ip,
+            sp,
+            symbol_address,
+            cfa,
+            registers,
+            is_return_address,
+        }
+    }
+
+    /// Clones this frame along with the values of `PRESERVED_REGISTERS`,
+    /// which `clone` leaves out as reading them is a call into the unwinder
+    /// for each one.
+    pub(crate) fn clone_with_registers(&self) -> Frame {
+        let mut frame = self.clone();
+        if let (Frame::Raw(_), Frame::Cloned { registers, .. }) = (self, &mut frame) {
+            for (value, &n) in registers.iter_mut().zip(PRESERVED_REGISTERS) {
+                *value = self.register(n);
+            }
+        }
+        frame
+    }
 }
 
 impl Clone for Frame {
     fn clone(&self) -> Frame {
+        let registers = match *self {
+            Frame::Raw(_) => [None; PRESERVED_REGISTERS.len()],
+            Frame::Cloned { registers, .. } => registers,
+        };
         Frame::Cloned {
             ip: self.ip(),
             sp: self.sp(),
             symbol_address: self.symbol_address(),
+            cfa: self.cfa(),
+            registers,
+            is_return_address: self.is_return_address(),
         }
     }
 }
@@ -163,6 +239,10 @@ mod uw {
         ))] {
             extern "C" {
                 pub fn _Unwind_GetIP(ctx: *mut _Unwind_Context) -> libc::uintptr_t;
+                pub fn _Unwind_GetIPInfo(
+                    ctx: *mut _Unwind_Context,
+                    ip_before_insn: *mut libc::c_int,
+                ) -> libc::uintptr_t;
                 pub fn _Unwind_FindEnclosingFunction(pc: *mut c_void) -> *mut c_void;
 
                 #[cfg(not(all(target_os = "linux", target_arch = "s390x")))]
@@ -174,6 +254,10 @@ mod uw {
                 #[link_name = "_Unwind_GetCFA"]
                 pub fn get_sp(ctx: *mut _Unwind_Context) -> libc::uintptr_t;
 
+                #[link_name = "_Unwind_GetCFA"]
+                fn unwind_get_cfa(ctx: *mut _Unwind_Context) -> libc::uintptr_t;
+
+                pub fn _Unwind_GetGR(ctx: *mut _Unwind_Context, index: libc::c_int) -> libc::uintptr_t;
             }
 
             // s390x uses a biased CFA value, therefore we need to use
@@ -181,11 +265,21 @@ mod uw {
             // instead of relying on _Unwind_GetCFA.
             #[cfg(all(target_os = "linux", target_arch = "s390x"))]
             pub unsafe fn get_sp(ctx: *mut _Unwind_Context) -> libc::uintptr_t {
-                extern "C" {
-                    pub fn _Unwind_GetGR(ctx: *mut _Unwind_Context, index: libc::c_int) -> libc::uintptr_t;
-                }
                 _Unwind_GetGR(ctx, 15)
             }
+
+            // The same function, for when it's the CFA that's wanted. Only
+            // libgcc returns the actual CFA, whereas LLVM's libunwind and the
+            // one above return the SP. Which unwinder is linked in can't be
+            // told at runtime, so the CFA is only reported for the targets
+            // which always link libgcc, leaving out s390x for its bias.
+            pub unsafe fn get_cfa(ctx: *mut _Unwind_Context) -> Option<libc::uintptr_t> {
+                if cfg!(all(target_env = "gnu", not(target_arch = "s390x"))) {
+                    Some(unwind_get_cfa(ctx))
+                } else {
+                    None
+                }
+            }
         } else {
             // On android and arm, the function `_Unwind_GetIP` and a bunch of
             // others are macros, so we define functions containing the
@@ -242,6 +336,15 @@ mod uw {
                 (val & !1) as libc::uintptr_t
             }
 
+            // ARM's EHABI doesn't mark frames interrupted by signals.
+            pub unsafe fn _Unwind_GetIPInfo(
+                ctx: *mut _Unwind_Context,
+                ip_before_insn: *mut libc::c_int,
+            ) -> libc::uintptr_t {
+                *ip_before_insn = 0;
+                _Unwind_GetIP(ctx)
+            }
+
             // R13 is the stack pointer on arm.
             const SP: _Unwind_Word = 13;
 
@@ -258,6 +361,25 @@ mod uw {
                 val as libc::uintptr_t
             }
 
+            // ARM's EHABI has no CFA of its own, which libgcc makes up for by
+            // returning the stack pointer, so there's no CFA to report.
+            pub unsafe fn get_cfa(_ctx: *mut _Unwind_Context) -> Option<libc::uintptr_t> {
+                None
+            }
+
+            pub unsafe fn _Unwind_GetGR(ctx: *mut _Unwind_Context, index: libc::c_int) -> libc::uintptr_t {
+                let mut val: _Unwind_Word = 0;
+                let ptr = &mut val as *mut _Unwind_Word;
+                let _ = _Unwind_VRS_Get(
+                    ctx,
+                    _Unwind_VRS_RegClass::_UVRSC_CORE,
+                    index as _Unwind_Word,
+                    _Unwind_VRS_DataRepresentation::_UVRSD_UINT32,
+                    ptr as *mut c_void,
+                );
+                val as libc::uintptr_t
+            }
+
             // This function also doesn't exist on Android or ARM/Linux, so make it
             // a no-op.
             pub unsafe fn _Unwind_FindEnclosingFunction(pc: *mut c_void) -> *mut c_void {
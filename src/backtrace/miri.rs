@@ -54,6 +54,22 @@ impl Frame {
     pub fn module_base_address(&self) -> Option<*mut c_void> {
         None
     }
+
+    pub fn cfa(&self) -> Option<*mut c_void> {
+        None
+    }
+
+    pub fn register(&self, _n: u16) -> Option<usize> {
+        None
+    }
+
+    pub(crate) fn clone_with_registers(&self) -> Frame {
+        self.clone()
+    }
+
+    pub fn is_return_address(&self) -> bool {
+        true
+    }
 }
 
 pub fn trace<F: FnMut(&super::Frame) -> bool>(cb: F) {
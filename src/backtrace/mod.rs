@@ -1,5 +1,11 @@
 use core::ffi::c_void;
 use core::fmt;
+#[cfg(feature = "frame-pointers")]
+use core::mem;
+#[cfg(feature = "frame-pointers")]
+use core::ops::Range;
+#[cfg(feature = "frame-pointers")]
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 /// Inspects the current call-stack, passing all active frames into the closure
 /// provided to calculate a stack trace.
@@ -66,6 +72,151 @@ pub unsafe fn trace_unsynchronized<F: FnMut(&Frame) -> bool>(mut cb: F) {
     trace_imp(&mut cb)
 }
 
+/// Records the instruction and stack pointers of the current call-stack into
+/// `frames`, returning the number of frames recorded.
+///
+/// Unlike `trace` this doesn't take this crate's lock and doesn't allocate
+/// itself, which makes it suitable for signal handlers, allocator hooks and
+/// similar contexts where the rest of this crate isn't, as far as the unwinder
+/// in use allows for it (see below). Frames are recorded top-down just like
+/// `trace` yields them, starting with this function itself, and recording
+/// stops once `frames` is full.
+///
+/// The recorded addresses can later be passed to `resolve`, outside of the
+/// signal handler.
+///
+/// # Async-signal safety
+///
+/// Whether walking the stack takes locks or allocates is up to the unwinder:
+///
+/// * The `frame-pointers` unwinder takes no locks and never allocates, and so
+///   is async-signal-safe, provided a hook reporting stack limits is
+///   installed, see `set_stack_limits_hook`. Without one it looks the limits
+///   up with `pthread_getattr_np`, which allocates.
+/// * The `gimli-unwind` unwinder never allocates, but looks up unwind
+///   information with `dl_iterate_phdr`, which holds the dynamic loader's
+///   lock. That's safe as long as the signal doesn't interrupt `dlopen` or
+///   `dlclose` on the same thread.
+/// * The system unwinder, used unless one of the above is enabled, makes no
+///   such guarantees. libgcc's `_Unwind_Find_FDE` takes a lock of its own as
+///   well as the dynamic loader's, and may allocate the first time it's used,
+///   so a signal interrupting the unwinder or the dynamic loader may deadlock.
+///   Enable `gimli-unwind` or `frame-pointers` where that matters.
+///
+/// # Required features
+///
+/// This function is available without the `std` feature of the `backtrace`
+/// crate. It is not available on Windows, where walking the stack requires
+/// holding a lock.
+///
+/// # Example
+///
+/// ```
+/// use std::mem::MaybeUninit;
+///
+/// let mut frames = [MaybeUninit::uninit(); 64];
+/// let len = backtrace::trace_into(&mut frames);
+/// for frame in &frames[..len] {
+///     let frame = unsafe { frame.assume_init() };
+///     println!("{:?}", frame.ip());
+/// }
+/// ```
+#[cfg(not(windows))]
+pub fn trace_into(frames: &mut [core::mem::MaybeUninit<RawFrame>]) -> usize {
+    let mut len = 0;
+    if frames.is_empty() {
+        return len;
+    }
+    // Safety: the unsynchronized part of this is only a problem for dbghelp on
+    // Windows, where this isn't available.
+    unsafe {
+        trace_unsynchronized(|frame| {
+            frames[len] = core::mem::MaybeUninit::new(RawFrame {
+                ip: frame.ip(),
+                sp: frame.sp(),
+            });
+            len += 1;
+            len < frames.len()
+        });
+    }
+    len
+}
+
+/// The addresses of one frame of a backtrace, as recorded by `trace_into`.
+#[derive(Copy, Clone, Debug)]
+pub struct RawFrame {
+    ip: *mut c_void,
+    sp: *mut c_void,
+}
+
+// These are plain addresses, which don't have to be dereferenced to be useful.
+unsafe impl Send for RawFrame {}
+unsafe impl Sync for RawFrame {}
+
+impl RawFrame {
+    /// Returns the instruction pointer of this frame, see `Frame::ip`.
+    pub fn ip(&self) -> *mut c_void {
+        self.ip
+    }
+
+    /// Returns the stack pointer of this frame, see `Frame::sp`.
+    pub fn sp(&self) -> *mut c_void {
+        self.sp
+    }
+}
+
+/// Inspects the call-stack of a context interrupted by a signal, passing all
+/// of its frames into the closure provided.
+///
+/// This is like `trace`, except that instead of starting at the caller it
+/// starts at the instruction the signal interrupted, as recorded in the
+/// registers saved in `context`. When called from a `SIGSEGV` handler the first
+/// frame is therefore the faulting one, and neither the signal handler nor the
+/// kernel's signal trampoline show up in the backtrace.
+///
+/// The stack is walked with this crate's own unwinder, which reads the
+/// `.eh_frame` sections of the loaded objects directly, regardless of which
+/// unwinder `trace` uses.
+///
+/// # Safety
+///
+/// `context` must point to the `ucontext_t` passed as the third argument to a
+/// signal handler installed with `SA_SIGINFO`, and the stack it describes must
+/// not have been unwound yet, which is the case while the handler is running.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default. It's only available
+/// on Linux on x86_64 and aarch64.
+#[cfg(all(
+    feature = "std",
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+    not(miri),
+))]
+pub unsafe fn trace_from_context<F: FnMut(&Frame) -> bool>(context: *const c_void, cb: F) {
+    let _guard = crate::lock::lock();
+    trace_from_context_unsynchronized(context, cb)
+}
+
+/// Same as `trace_from_context`, only unsynchronized.
+///
+/// This function does not have synchronization guarantees but is available
+/// when the `std` feature of this crate isn't compiled in. See the
+/// `trace_from_context` function for more documentation.
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+    not(miri),
+))]
+pub unsafe fn trace_from_context_unsynchronized<F: FnMut(&Frame) -> bool>(
+    context: *const c_void,
+    mut cb: F,
+) {
+    gimli::trace_from_context(context, &mut cb)
+}
+
 /// A trait representing one frame of a backtrace, yielded to the `trace`
 /// function of this crate.
 ///
@@ -114,6 +265,127 @@ impl Frame {
     pub fn module_base_address(&self) -> Option<*mut c_void> {
         self.inner.module_base_address()
     }
+
+    /// Returns the canonical frame address (CFA) of this frame, if the backend
+    /// knows it.
+    ///
+    /// The CFA is what DWARF call frame information describes a frame
+    /// relative to, and is normally the value of the stack pointer in the
+    /// calling frame right before the call. It's available from the
+    /// `gimli-unwind` unwinder, which also walks the stacks of signal
+    /// contexts, and from the system unwinder on targets which use libgcc for
+    /// it, that is those whose `target_env` is `gnu`. Other system unwinders,
+    /// such as LLVM's libunwind, return the stack pointer of this frame from
+    /// `_Unwind_GetCFA` instead, and libgcc returns a biased value on s390x
+    /// and no CFA of its own on ARM, so `None` is returned there.
+    pub fn cfa(&self) -> Option<*mut c_void> {
+        self.inner.cfa()
+    }
+
+    /// Returns the value the register `n` had in this frame, if the backend
+    /// knows it.
+    ///
+    /// Registers are numbered the way DWARF numbers them for the target
+    /// architecture. Only the registers a function has to preserve for its
+    /// caller can be recovered for frames other than the innermost one, so
+    /// `None` is returned for all others, as well as on architectures other
+    /// than x86, x86_64, ARM and AArch64. The stack pointer is left out too,
+    /// see `sp` and `cfa` for that. Registers are available from the system
+    /// unwinder and the `gimli-unwind` unwinder.
+    ///
+    /// Reading the registers isn't free with the system unwinder, so clones of
+    /// the frames it passes to `trace` don't keep them. The frames of a
+    /// `Backtrace` always do.
+    pub fn register(&self, n: u16) -> Option<usize> {
+        self.inner.register(n)
+    }
+
+    /// Clones this frame along with its registers, see `register`.
+    pub(crate) fn clone_with_registers(&self) -> Frame {
+        Frame {
+            inner: self.inner.clone_with_registers(),
+        }
+    }
+
+    /// Returns whether `ip` is a return address, as opposed to the address of
+    /// the instruction the frame was executing.
+    ///
+    /// The `ip` of most frames is where execution returns to once the frame
+    /// they called returns, which is the instruction *after* the call. When
+    /// such an address is resolved, one is subtracted from it first so the
+    /// call itself is looked up instead. The innermost frame of a context
+    /// interrupted by a signal (see `trace_from_context`) and frames
+    /// interrupted by signals further up the stack are exactly at the
+    /// instruction that was executing though, so `resolve_frame` leaves their
+    /// `ip` as is.
+    pub fn is_return_address(&self) -> bool {
+        self.inner.is_return_address()
+    }
+}
+
+// The registers which are preserved across calls, by their DWARF numbers. The
+// unwinder only recovers these for frames other than the innermost one, and
+// libgcc crashes when asked for others it doesn't know where to find. That
+// includes the stack pointer, which is what `sp` and `cfa` are for instead.
+// Unused unless the system unwinder is, or frames are serialized.
+cfg_if::cfg_if! {
+    if #[cfg(target_arch = "x86_64")] {
+        #[allow(dead_code)]
+        pub(crate) const PRESERVED_REGISTERS: &[u16] = &[3, 6, 12, 13, 14, 15];
+    } else if #[cfg(target_arch = "x86")] {
+        #[allow(dead_code)]
+        pub(crate) const PRESERVED_REGISTERS: &[u16] = &[3, 5, 6, 7];
+    } else if #[cfg(target_arch = "aarch64")] {
+        #[allow(dead_code)]
+        pub(crate) const PRESERVED_REGISTERS: &[u16] =
+            &[19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29];
+    } else if #[cfg(target_arch = "arm")] {
+        #[allow(dead_code)]
+        pub(crate) const PRESERVED_REGISTERS: &[u16] = &[4, 5, 6, 7, 8, 9, 10, 11];
+    } else {
+        #[allow(dead_code)]
+        pub(crate) const PRESERVED_REGISTERS: &[u16] = &[];
+    }
+}
+
+/// Installs a function reporting the bounds of the stack that the calling
+/// thread is running on, used by the frame-pointer unwinder.
+///
+/// The `frame-pointers` unwinder only ever reads frame records from inside
+/// these bounds, which is what keeps it from faulting on a corrupt chain. If no
+/// hook is installed, or the hook returns `None`, the bounds are queried from
+/// pthreads on Linux and Android; on other platforms the unwinder won't walk
+/// anything unless a hook knows the bounds.
+///
+/// Kernels and other environments without pthreads should install a hook
+/// returning the current task's stack. Querying pthreads isn't
+/// async-signal-safe (glibc allocates in `pthread_getattr_np`), so a hook is
+/// also needed to take backtraces from signal handlers. A hook that doesn't
+/// know the bounds of a thread's stack but wants to avoid the fallback can
+/// return an empty range, which makes the unwinder not walk anything.
+///
+/// The hook must be async-signal-safe itself if backtraces are taken from
+/// signal handlers, and must not call back into this crate.
+///
+/// # Required features
+///
+/// This function requires the `frame-pointers` feature of the `backtrace`
+/// crate to be enabled.
+#[cfg(feature = "frame-pointers")]
+pub fn set_stack_limits_hook(hook: Option<fn() -> Option<Range<usize>>>) {
+    STACK_LIMITS_HOOK.store(hook.map_or(0, |hook| hook as usize), Ordering::SeqCst);
+}
+
+#[cfg(feature = "frame-pointers")]
+static STACK_LIMITS_HOOK: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "frame-pointers")]
+#[allow(dead_code)]
+fn stack_limits_hook() -> Option<fn() -> Option<Range<usize>>> {
+    match STACK_LIMITS_HOOK.load(Ordering::SeqCst) {
+        0 => None,
+        hook => Some(unsafe { mem::transmute::<usize, fn() -> Option<Range<usize>>>(hook) }),
+    }
 }
 
 impl fmt::Debug for Frame {
@@ -125,6 +397,23 @@ impl fmt::Debug for Frame {
     }
 }
 
+// The pure-Rust unwinder is the only one able to start from the registers
+// saved in a signal context, so it's always around where `trace_from_context`
+// is supported, and also serves as the `gimli-unwind` backend of `trace`. With
+// the `ptrace` and `coredump` features it unwinds other processes as well.
+#[cfg(all(
+    any(
+        target_os = "linux",
+        all(
+            feature = "gimli-unwind",
+            any(target_os = "android", target_os = "freebsd", target_os = "fuchsia"),
+        ),
+    ),
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+    not(miri),
+))]
+pub(crate) mod gimli;
+
 cfg_if::cfg_if! {
     // This needs to come first, to ensure that
     // Miri takes priority over the host platform
@@ -132,6 +421,29 @@ cfg_if::cfg_if! {
         pub(crate) mod miri;
         use self::miri::trace as trace_imp;
         pub(crate) use self::miri::Frame as FrameImp;
+    } else if #[cfg(
+        all(
+            feature = "frame-pointers",
+            any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"),
+        )
+    )] {
+        mod frame_pointer;
+        use self::frame_pointer::trace as trace_imp;
+        pub(crate) use self::frame_pointer::Frame as FrameImp;
+    } else if #[cfg(
+        all(
+            feature = "gimli-unwind",
+            any(
+                target_os = "android",
+                target_os = "freebsd",
+                target_os = "fuchsia",
+                target_os = "linux",
+            ),
+            any(target_arch = "x86_64", target_arch = "aarch64"),
+        )
+    )] {
+        use self::gimli::trace as trace_imp;
+        pub(crate) use self::gimli::Frame as FrameImp;
     } else if #[cfg(
         any(
             all(
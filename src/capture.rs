@@ -1,7 +1,9 @@
+use crate::symbolize::{find_modules, resolve_exact, resolve_with_modules, ResolveWhat};
 use crate::PrintFmt;
-use crate::{resolve, resolve_frame, trace, BacktraceFmt, Symbol, SymbolName};
+use crate::{resolve, resolve_frame, trace, BacktraceFmt, Module, Symbol, SymbolName};
 use std::ffi::c_void;
 use std::fmt;
+use std::mem;
 use std::path::{Path, PathBuf};
 use std::prelude::v1::*;
 
@@ -49,6 +51,11 @@ fn _assert_send_sync() {
 pub struct BacktraceFrame {
     frame: Frame,
     symbols: Option<Vec<BacktraceSymbol>>,
+    module: Option<BacktraceModule>,
+    // Whether `module` is yet to be looked up among the modules loaded into
+    // the current process. That's left until it's needed, as it's about as
+    // expensive as capturing the backtrace in the first place.
+    module_pending: bool,
 }
 
 #[derive(Clone)]
@@ -59,6 +66,9 @@ enum Frame {
         ip: usize,
         symbol_address: usize,
         module_base_address: Option<usize>,
+        cfa: Option<usize>,
+        registers: Vec<(u16, usize)>,
+        is_return_address: bool,
     },
 }
 
@@ -86,6 +96,41 @@ impl Frame {
             } => module_base_address.map(|addr| addr as *mut c_void),
         }
     }
+
+    fn cfa(&self) -> Option<*mut c_void> {
+        match *self {
+            Frame::Raw(ref f) => f.cfa(),
+            Frame::Deserialized { cfa, .. } => cfa.map(|addr| addr as *mut c_void),
+        }
+    }
+
+    fn register(&self, n: u16) -> Option<usize> {
+        match *self {
+            Frame::Raw(ref f) => f.register(n),
+            Frame::Deserialized { ref registers, .. } => registers
+                .iter()
+                .find(|&&(register, _)| register == n)
+                .map(|&(_, value)| value),
+        }
+    }
+
+    fn is_return_address(&self) -> bool {
+        match *self {
+            Frame::Raw(ref f) => f.is_return_address(),
+            Frame::Deserialized {
+                is_return_address, ..
+            } => is_return_address,
+        }
+    }
+
+    /// Returns the values of all registers that are known, for serialization.
+    #[cfg(any(feature = "serde", feature = "serialize-rustc"))]
+    fn registers(&self) -> Vec<(u16, usize)> {
+        crate::backtrace::PRESERVED_REGISTERS
+            .iter()
+            .filter_map(|&n| Some((n, self.register(n)?)))
+            .collect()
+    }
 }
 
 /// Captured version of a symbol in a backtrace.
@@ -106,8 +151,64 @@ pub struct BacktraceSymbol {
     filename: Option<PathBuf>,
     lineno: Option<u32>,
     colno: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    size: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    offset: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    inlined: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    call_filename: Option<PathBuf>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    call_lineno: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    call_colno: Option<u32>,
+}
+
+/// Identity of the module (executable or shared library) that a frame in a
+/// backtrace belongs to.
+///
+/// This is returned from `BacktraceFrame::module`. Together with `BacktraceFrame::module_offset` it
+/// identifies a frame's code independently of where the module happened to be
+/// loaded, which allows de-duplicating and symbolicating frames on a machine
+/// other than the one they were captured on.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize-rustc", derive(RustcDecodable, RustcEncodable))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct BacktraceModule {
+    path: PathBuf,
+    build_id: Option<Vec<u8>>,
+    base_address: usize,
 }
 
+/// A builder for capturing a `Backtrace` with only some of the frames of the
+/// stack, returned from `Backtrace::builder`.
+///
+/// Frames which are skipped or beyond the frames wanted are never stored, and
+/// the stack isn't walked any further than it needs to be, which makes this
+/// cheaper than capturing a whole backtrace and discarding frames afterwards
+/// for deep stacks.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+pub struct BacktraceBuilder<'a> {
+    skip: usize,
+    skip_until: usize,
+    max_frames: Option<usize>,
+    stop_at: Option<StopAt<'a>>,
+    resolve: bool,
+}
+
+/// The predicate given to `BacktraceBuilder::stop_at`.
+type StopAt<'a> = Box<dyn FnMut(&crate::Frame) -> bool + 'a>;
+
 impl Backtrace {
     /// Captures a backtrace at the callsite of this function, returning an
     /// owned representation.
@@ -170,14 +271,73 @@ impl Backtrace {
         Self::create(Self::new_unresolved as usize)
     }
 
+    /// Returns a builder for capturing a backtrace which leaves out some of
+    /// the frames of the stack, see `BacktraceBuilder`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backtrace::Backtrace;
+    ///
+    /// // The ten frames closest to the call site, minus the call site itself.
+    /// let current_backtrace = Backtrace::builder().skip(1).max_frames(10).capture();
+    /// assert!(current_backtrace.frames().len() <= 10);
+    /// ```
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn builder<'a>() -> BacktraceBuilder<'a> {
+        BacktraceBuilder {
+            skip: 0,
+            skip_until: BacktraceBuilder::capture as *const () as usize,
+            max_frames: None,
+            stop_at: None,
+            resolve: true,
+        }
+    }
+
+    /// Captures the backtrace of a context interrupted by a signal, starting
+    /// with the interrupted frame.
+    ///
+    /// This is the `Backtrace` counterpart of `trace_from_context`, see there
+    /// for how the stack is walked. Neither symbols nor the modules of the
+    /// frames are looked up here, so the crate's lock and symbol cache aren't
+    /// touched while the handler runs. They're looked up by `resolve` (or
+    /// `resolve_with_modules`, and modules also by `BacktraceFrame::module`),
+    /// none of which should be called until the handler has returned.
+    ///
+    /// # Safety
+    ///
+    /// `context` must point to the `ucontext_t` passed as the third argument
+    /// to a signal handler installed with `SA_SIGINFO`, and the handler must
+    /// still be running.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default. It's only
+    /// available on Linux on x86_64 and aarch64.
+    #[cfg(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64"),
+        not(miri),
+    ))]
+    pub unsafe fn from_context(context: *const c_void) -> Backtrace {
+        let mut frames = Vec::new();
+        crate::trace_from_context(context, |frame| {
+            frames.push(BacktraceFrame::captured(frame.clone_with_registers()));
+            true
+        });
+        Backtrace::from(frames)
+    }
+
     fn create(ip: usize) -> Backtrace {
         let mut frames = Vec::new();
         let mut actual_start_index = None;
         trace(|frame| {
-            frames.push(BacktraceFrame {
-                frame: Frame::Raw(frame.clone()),
-                symbols: None,
-            });
+            frames.push(BacktraceFrame::captured(frame.clone_with_registers()));
 
             if frame.symbol_address() as usize == ip && actual_start_index.is_none() {
                 actual_start_index = Some(frames.len());
@@ -191,6 +351,46 @@ impl Backtrace {
         }
     }
 
+    // Looks up the modules of the frames whose module is still pending, all at
+    // once.
+    fn find_modules(&mut self) {
+        let mut frames = self
+            .frames
+            .iter_mut()
+            .filter(|f| f.module_pending)
+            .collect::<Vec<_>>();
+        if frames.is_empty() {
+            return;
+        }
+        let addrs = frames.iter().map(|f| f.ip()).collect::<Vec<_>>();
+        find_modules(&addrs, |i, module| {
+            frames[i].module = Some(BacktraceModule::from(module));
+        });
+        for frame in frames {
+            frame.module_pending = false;
+        }
+    }
+
+    /// Creates a backtrace out of frames captured in another process, whose
+    /// loaded images are described by `modules`.
+    #[allow(dead_code)]
+    pub(crate) fn with_foreign_modules(
+        mut frames: Vec<BacktraceFrame>,
+        modules: &[Module],
+    ) -> Backtrace {
+        for frame in frames.iter_mut() {
+            let ip = frame.ip() as usize;
+            let module = modules.iter().find(|module| module.contains(ip));
+            frame.module = module.map(BacktraceModule::from);
+            frame.module_pending = false;
+        }
+
+        Backtrace {
+            frames,
+            actual_start_index: 0,
+        }
+    }
+
     /// Returns the frames from when this backtrace was captured.
     ///
     /// The first entry of this slice is likely the function `Backtrace::new`,
@@ -216,28 +416,215 @@ impl Backtrace {
     /// This function requires the `std` feature of the `backtrace` crate to be
     /// enabled, and the `std` feature is enabled by default.
     pub fn resolve(&mut self) {
+        self.find_modules();
         for frame in self.frames.iter_mut().filter(|f| f.symbols.is_none()) {
             let mut symbols = Vec::new();
             {
                 let sym = |symbol: &Symbol| {
-                    symbols.push(BacktraceSymbol {
-                        name: symbol.name().map(|m| m.as_bytes().to_vec()),
-                        addr: symbol.addr().map(|a| a as usize),
-                        filename: symbol.filename().map(|m| m.to_owned()),
-                        lineno: symbol.lineno(),
-                        colno: symbol.colno(),
-                    });
+                    symbols.push(BacktraceSymbol::from(symbol));
                 };
                 match frame.frame {
                     Frame::Raw(ref f) => resolve_frame(f, sym),
-                    Frame::Deserialized { ip, .. } => {
-                        resolve(ip as *mut c_void, sym);
-                    }
+                    Frame::Deserialized {
+                        ip,
+                        is_return_address: true,
+                        ..
+                    } => resolve(ip as *mut c_void, sym),
+                    Frame::Deserialized { ip, .. } => resolve_exact(ip as *mut c_void, sym),
                 }
             }
             frame.symbols = Some(symbols);
         }
     }
+
+    /// Resolves all addresses in this backtrace against the `modules` given,
+    /// instead of against the libraries loaded into the current process.
+    ///
+    /// This is intended for symbolicating a backtrace which was captured with
+    /// `new_unresolved` in another process, for example after being sent
+    /// across the network with the `serde` feature of this crate. The
+    /// `modules` should describe the images loaded into that process at the
+    /// time the backtrace was captured, and their paths should point to
+    /// copies of those images which are accessible to the current process.
+    ///
+    /// Frames which recorded the build ID of their module (see
+    /// `BacktraceFrame::module`) are matched up with the module in `modules`
+    /// carrying the same build ID, in which case only the offset of the frame
+    /// within its module matters and the module's bias may differ from the
+    /// one it had in the capturing process.
+    ///
+    /// Frames which have already been resolved are left untouched, and frames
+    /// whose address doesn't fall within any of `modules` are resolved to an
+    /// empty list of symbols.
+    ///
+    /// The debuginfo of `modules` is cached like that of the libraries loaded
+    /// into the current process, so resolving further backtraces against
+    /// modules backed by the same files doesn't parse it again.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn resolve_with_modules(&mut self, modules: &[Module]) {
+        self.find_modules();
+        let mut frames = self
+            .frames
+            .iter_mut()
+            .filter(|f| f.symbols.is_none())
+            .collect::<Vec<_>>();
+        let addrs = frames
+            .iter()
+            .map(|f| {
+                let ip = f.ip_within(modules);
+                if f.frame.is_return_address() {
+                    ResolveWhat::Address(ip)
+                } else {
+                    ResolveWhat::ExactAddress(ip)
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut symbols = vec![Vec::new(); frames.len()];
+        resolve_with_modules(modules, &addrs, |i, symbol| {
+            symbols[i].push(BacktraceSymbol::from(symbol));
+        });
+        for (frame, symbols) in frames.iter_mut().zip(symbols) {
+            frame.symbols = Some(symbols);
+        }
+    }
+}
+
+impl<'a> BacktraceBuilder<'a> {
+    /// Leaves out the `n` innermost frames, after those left out by
+    /// `skip_until`.
+    pub fn skip(mut self, n: usize) -> Self {
+        self.skip = n;
+        self
+    }
+
+    /// Leaves out all frames up to and including the innermost frame of the
+    /// function starting at `symbol_address`, as found through
+    /// `Frame::symbol_address`.
+    ///
+    /// By default this is `BacktraceBuilder::capture`, so the backtrace starts
+    /// with its caller. Like `Backtrace::new` nothing is left out if no frame
+    /// of the function is found, which is the case if it's been inlined or on
+    /// platforms where `Frame::symbol_address` is the ip itself. On those
+    /// platforms the search is given up right away, but otherwise the whole
+    /// stack has to be walked to find out that the function isn't on it.
+    pub fn skip_until(mut self, symbol_address: *mut c_void) -> Self {
+        self.skip_until = symbol_address as usize;
+        self
+    }
+
+    /// Stops walking the stack once `n` frames have been captured.
+    pub fn max_frames(mut self, n: usize) -> Self {
+        self.max_frames = Some(n);
+        self
+    }
+
+    /// Stops walking the stack at the first frame for which `stop` returns
+    /// true, which is left out of the backtrace.
+    ///
+    /// `stop` is only called for frames which aren't skipped.
+    pub fn stop_at<F>(mut self, stop: F) -> Self
+    where
+        F: FnMut(&crate::Frame) -> bool + 'a,
+    {
+        self.stop_at = Some(Box::new(stop));
+        self
+    }
+
+    /// Sets whether the backtrace is resolved before it's returned, which it
+    /// is by default. Not resolving it is the same as capturing it with
+    /// `Backtrace::new_unresolved`.
+    pub fn resolve(mut self, resolve: bool) -> Self {
+        self.resolve = resolve;
+        self
+    }
+
+    /// Captures the backtrace at the callsite of this function.
+    #[inline(never)] // want to make sure there's a frame here to remove
+    pub fn capture(self) -> Backtrace {
+        let BacktraceBuilder {
+            mut skip,
+            skip_until,
+            max_frames,
+            mut stop_at,
+            resolve,
+        } = self;
+        let max_frames = max_frames.unwrap_or(usize::max_value());
+        let mut frames = Vec::new();
+        // Frames before the one of `skip_until` have to be kept until it's
+        // found, in case it's never found and nothing should be skipped, but
+        // no more of them than could end up in the backtrace then.
+        let max_skipped = skip.saturating_add(max_frames);
+        let mut skipped = Vec::new();
+        let mut searching = true;
+        trace(|frame| {
+            if searching {
+                let symbol_address = frame.symbol_address();
+                if symbol_address as usize == skip_until {
+                    searching = false;
+                    skipped = Vec::new();
+                    return true;
+                }
+                // Where the functions of frames can't be told apart, which is
+                // the case if their `symbol_address` is just the ip, there's no
+                // point in looking any further.
+                if symbol_address != frame.ip() {
+                    if skipped.len() < max_skipped {
+                        skipped.push(frame.clone_with_registers());
+                    }
+                    return true;
+                }
+                searching = false;
+                for skipped in mem::take(&mut skipped).iter() {
+                    if !take_frame(skipped, &mut skip, &mut frames, max_frames, &mut stop_at) {
+                        return false;
+                    }
+                }
+            }
+            take_frame(frame, &mut skip, &mut frames, max_frames, &mut stop_at)
+        });
+        if searching {
+            for frame in skipped.iter() {
+                if !take_frame(frame, &mut skip, &mut frames, max_frames, &mut stop_at) {
+                    break;
+                }
+            }
+        }
+
+        let mut bt = Backtrace::from(frames);
+        if resolve {
+            bt.resolve();
+        }
+        bt
+    }
+}
+
+/// Adds `frame` to `frames` unless it's skipped, returning whether to keep on
+/// walking the stack.
+fn take_frame(
+    frame: &crate::Frame,
+    skip: &mut usize,
+    frames: &mut Vec<BacktraceFrame>,
+    max_frames: usize,
+    stop_at: &mut Option<StopAt<'_>>,
+) -> bool {
+    if frames.len() >= max_frames {
+        return false;
+    }
+    if *skip > 0 {
+        *skip -= 1;
+        return true;
+    }
+    if let Some(stop) = stop_at {
+        if stop(frame) {
+            return false;
+        }
+    }
+    frames.push(BacktraceFrame::captured(frame.clone_with_registers()));
+    frames.len() < max_frames
 }
 
 impl From<Vec<BacktraceFrame>> for Backtrace {
@@ -254,6 +641,36 @@ impl From<crate::Frame> for BacktraceFrame {
         BacktraceFrame {
             frame: Frame::Raw(frame),
             symbols: None,
+            module: None,
+            module_pending: false,
+        }
+    }
+}
+
+impl<'a> From<&'a Module> for BacktraceModule {
+    fn from(module: &'a Module) -> BacktraceModule {
+        BacktraceModule {
+            path: module.path().to_owned(),
+            build_id: module.build_id().map(|id| id.to_vec()),
+            base_address: module.base_address(),
+        }
+    }
+}
+
+impl<'a> From<&'a Symbol> for BacktraceSymbol {
+    fn from(symbol: &'a Symbol) -> BacktraceSymbol {
+        BacktraceSymbol {
+            name: symbol.name().map(|m| m.as_bytes().to_vec()),
+            addr: symbol.addr().map(|a| a as usize),
+            filename: symbol.filename().map(|m| m.to_owned()),
+            lineno: symbol.lineno(),
+            colno: symbol.colno(),
+            size: symbol.size(),
+            offset: symbol.offset(),
+            inlined: symbol.is_inlined(),
+            call_filename: symbol.call_filename().map(|m| m.to_owned()),
+            call_lineno: symbol.call_lineno(),
+            call_colno: symbol.call_colno(),
         }
     }
 }
@@ -265,6 +682,15 @@ impl Into<Vec<BacktraceFrame>> for Backtrace {
 }
 
 impl BacktraceFrame {
+    // A frame captured in the current process, whose module can be looked up
+    // among those loaded into it.
+    pub(crate) fn captured(frame: crate::Frame) -> BacktraceFrame {
+        BacktraceFrame {
+            module_pending: true,
+            ..BacktraceFrame::from(frame)
+        }
+    }
+
     /// Same as `Frame::ip`
     ///
     /// # Required features
@@ -294,7 +720,90 @@ impl BacktraceFrame {
     pub fn module_base_address(&self) -> Option<*mut c_void> {
         self.frame
             .module_base_address()
-            .map(|addr| addr as *mut c_void)
+            .or_else(|| self.module().map(|m| m.base_address as *mut c_void))
+    }
+
+    /// Same as `Frame::cfa`
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn cfa(&self) -> Option<*mut c_void> {
+        self.frame.cfa()
+    }
+
+    /// Same as `Frame::register`
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn register(&self, n: u16) -> Option<usize> {
+        self.frame.register(n)
+    }
+
+    /// Same as `Frame::is_return_address`
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn is_return_address(&self) -> bool {
+        self.frame.is_return_address()
+    }
+
+    /// Returns the module that this frame's instruction pointer belongs to.
+    ///
+    /// This is available for backtraces that haven't been resolved yet, and
+    /// is serialized along with the frame. It's `None` if the module couldn't
+    /// be determined, or if this frame wasn't captured through `Backtrace` or
+    /// `BacktraceBuilder`.
+    ///
+    /// Looking up the module is left until it's first needed, to keep
+    /// capturing backtraces cheap: it's recorded by `Backtrace::resolve` and
+    /// `Backtrace::resolve_with_modules`, and until then it's looked up again
+    /// among the modules currently loaded each time it's asked for.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn module(&self) -> Option<BacktraceModule> {
+        if !self.module_pending {
+            return self.module.clone();
+        }
+        let mut found = None;
+        find_modules(&[self.ip()], |_, module| {
+            found = Some(BacktraceModule::from(module));
+        });
+        found
+    }
+
+    /// Returns the offset of this frame's instruction pointer from the base
+    /// address of its module, if the module is known.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn module_offset(&self) -> Option<usize> {
+        let module = self.module()?;
+        Some((self.ip() as usize).wrapping_sub(module.base_address))
+    }
+
+    // Returns the address of this frame in the address space described by
+    // `modules`, relocating it through the build ID of its module if possible.
+    fn ip_within(&self, modules: &[Module]) -> *mut c_void {
+        let relocated = self.module().and_then(|module| {
+            let build_id = module.build_id.as_ref()?;
+            let target = modules
+                .iter()
+                .find(|m| m.build_id() == Some(&build_id[..]))?;
+            let offset = (self.ip() as usize).wrapping_sub(module.base_address);
+            Some(target.base_address().wrapping_add(offset) as *mut c_void)
+        });
+        relocated.unwrap_or_else(|| self.ip())
     }
 
     /// Returns the list of symbols that this frame corresponds to.
@@ -316,6 +825,41 @@ impl BacktraceFrame {
     }
 }
 
+impl BacktraceModule {
+    /// Returns the path of the module's file in the process which captured the
+    /// backtrace.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the GNU build ID (or Mach-O UUID, or the GUID and age of the
+    /// PDB of a PE image) of the module, if it has one.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn build_id(&self) -> Option<&[u8]> {
+        self.build_id.as_ref().map(|id| &id[..])
+    }
+
+    /// Returns the address the module was loaded at in the process which
+    /// captured the backtrace.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn base_address(&self) -> *mut c_void {
+        self.base_address as *mut c_void
+    }
+}
+
 impl BacktraceSymbol {
     /// Same as `Symbol::name`
     ///
@@ -337,6 +881,26 @@ impl BacktraceSymbol {
         self.addr.map(|s| s as *mut c_void)
     }
 
+    /// Same as `Symbol::size`
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn size(&self) -> Option<usize> {
+        self.size
+    }
+
+    /// Same as `Symbol::offset`
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
     /// Same as `Symbol::filename`
     ///
     /// # Required features
@@ -366,6 +930,46 @@ impl BacktraceSymbol {
     pub fn colno(&self) -> Option<u32> {
         self.colno
     }
+
+    /// Same as `Symbol::is_inlined`
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn is_inlined(&self) -> bool {
+        self.inlined
+    }
+
+    /// Same as `Symbol::call_filename`
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn call_filename(&self) -> Option<&Path> {
+        self.call_filename.as_ref().map(|p| &**p)
+    }
+
+    /// Same as `Symbol::call_lineno`
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn call_lineno(&self) -> Option<u32> {
+        self.call_lineno
+    }
+
+    /// Same as `Symbol::call_colno`
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    pub fn call_colno(&self) -> Option<u32> {
+        self.call_colno
+    }
 }
 
 impl fmt::Debug for Backtrace {
@@ -428,6 +1032,7 @@ impl fmt::Debug for BacktraceSymbol {
             .field("filename", &self.filename())
             .field("lineno", &self.lineno())
             .field("colno", &self.colno())
+            .field("inlined", &self.is_inlined())
             .finish()
     }
 }
@@ -442,7 +1047,11 @@ mod rustc_serialize_impls {
         ip: usize,
         symbol_address: usize,
         module_base_address: Option<usize>,
+        cfa: Option<usize>,
+        registers: Vec<(u16, usize)>,
+        is_return_address: bool,
         symbols: Option<Vec<BacktraceSymbol>>,
+        module: Option<BacktraceModule>,
     }
 
     impl Decodable for BacktraceFrame {
@@ -456,8 +1065,13 @@ mod rustc_serialize_impls {
                     ip: frame.ip,
                     symbol_address: frame.symbol_address,
                     module_base_address: frame.module_base_address,
+                    cfa: frame.cfa,
+                    registers: frame.registers,
+                    is_return_address: frame.is_return_address,
                 },
                 symbols: frame.symbols,
+                module: frame.module,
+                module_pending: false,
             })
         }
     }
@@ -467,12 +1081,16 @@ mod rustc_serialize_impls {
         where
             E: Encoder,
         {
-            let BacktraceFrame { frame, symbols } = self;
+            let BacktraceFrame { frame, symbols, .. } = self;
             SerializedFrame {
                 ip: frame.ip() as usize,
                 symbol_address: frame.symbol_address() as usize,
                 module_base_address: frame.module_base_address().map(|addr| addr as usize),
+                cfa: frame.cfa().map(|addr| addr as usize),
+                registers: frame.registers(),
+                is_return_address: frame.is_return_address(),
                 symbols: symbols.clone(),
+                module: self.module(),
             }
             .encode(e)
         }
@@ -491,7 +1109,20 @@ mod serde_impls {
         ip: usize,
         symbol_address: usize,
         module_base_address: Option<usize>,
+        #[serde(default)]
+        cfa: Option<usize>,
+        #[serde(default)]
+        registers: Vec<(u16, usize)>,
+        #[serde(default = "return_address_default")]
+        is_return_address: bool,
         symbols: Option<Vec<BacktraceSymbol>>,
+        module: Option<BacktraceModule>,
+    }
+
+    // Frames serialized before this was recorded were all resolved as return
+    // addresses.
+    fn return_address_default() -> bool {
+        true
     }
 
     impl Serialize for BacktraceFrame {
@@ -499,12 +1130,16 @@ mod serde_impls {
         where
             S: Serializer,
         {
-            let BacktraceFrame { frame, symbols } = self;
+            let BacktraceFrame { frame, symbols, .. } = self;
             SerializedFrame {
                 ip: frame.ip() as usize,
                 symbol_address: frame.symbol_address() as usize,
                 module_base_address: frame.module_base_address().map(|addr| addr as usize),
+                cfa: frame.cfa().map(|addr| addr as usize),
+                registers: frame.registers(),
+                is_return_address: frame.is_return_address(),
                 symbols: symbols.clone(),
+                module: self.module(),
             }
             .serialize(s)
         }
@@ -521,8 +1156,13 @@ mod serde_impls {
                     ip: frame.ip,
                     symbol_address: frame.symbol_address,
                     module_base_address: frame.module_base_address,
+                    cfa: frame.cfa,
+                    registers: frame.registers,
+                    is_return_address: frame.is_return_address,
                 },
                 symbols: frame.symbols,
+                module: frame.module,
+                module_pending: false,
             })
         }
     }
@@ -0,0 +1,312 @@
+//! Capturing the backtraces of the threads of a process from a core dump.
+//!
+//! A Linux core file is an ELF file of type `ET_CORE`. Its `PT_LOAD` segments
+//! hold the memory the process had, and its `PT_NOTE` segment holds a
+//! `NT_PRSTATUS` note with the registers of each thread as well as a `NT_FILE`
+//! note listing the files mapped into the process. Only the memory the process
+//! wrote to is usually dumped, so anything else, like the code and unwind
+//! information of the objects it had loaded, is read from the mapped files
+//! instead, which have to still be around for this to work.
+
+use crate::backtrace::gimli::Registers;
+use crate::foreign::{ForeignProcess, Memory};
+use crate::{Backtrace, Module, ThreadBacktrace};
+use object::elf::{ET_CORE, NT_FILE, NT_PRSTATUS, PT_LOAD, PT_NOTE};
+use object::read::elf::{FileHeader, ProgramHeader};
+use object::{NativeEndian, U64};
+use std::convert::TryInto;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io;
+use std::mem;
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use std::prelude::v1::*;
+use std::ptr;
+
+type Elf = object::elf::FileHeader64<NativeEndian>;
+
+#[cfg(target_arch = "x86_64")]
+const MACHINE: u16 = object::elf::EM_X86_64;
+#[cfg(target_arch = "aarch64")]
+const MACHINE: u16 = object::elf::EM_AARCH64;
+
+// Offsets into `struct elf_prstatus` of the id of the thread and of its
+// registers, which are the same on all 64-bit architectures.
+const PRSTATUS_PID: usize = 32;
+const PRSTATUS_REGS: usize = 112;
+
+/// A core dump of a process, to capture the backtraces its threads had at the
+/// time it was dumped.
+///
+/// The files the process had mapped, as listed in the core file, are read for
+/// the unwind information and symbols of the objects it had loaded, so they
+/// have to still be where they were and unchanged. The process also has to
+/// have run on the same architecture as the current one.
+///
+/// # Required features
+///
+/// This requires the `coredump` feature of the `backtrace` crate to be
+/// enabled, and it's only available on Linux on x86_64 and aarch64.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> {
+/// let core = backtrace::CoreDump::open("core")?;
+/// for thread in core.backtraces() {
+///     println!("{:?}", thread);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct CoreDump {
+    threads: Vec<u32>,
+    registers: Vec<Registers>,
+    process: ForeignProcess<CoreMemory>,
+}
+
+/// The memory of the process, as far as it was dumped, or otherwise as found in
+/// the files mapped into it.
+struct CoreMemory {
+    file: File,
+    segments: Vec<Segment>,
+    files: Vec<MappedFile>,
+}
+
+/// A range of memory of the process, dumped at `offset` in the core file.
+struct Segment {
+    start: usize,
+    end: usize,
+    offset: u64,
+}
+
+/// A range of memory of the process mapped from `offset` in the file `path`.
+struct MappedFile {
+    start: usize,
+    end: usize,
+    offset: u64,
+    path: PathBuf,
+}
+
+impl CoreDump {
+    /// Opens the core file at `path`.
+    ///
+    /// Fails if the file can't be read, or isn't a core file of a process
+    /// which ran on the current architecture.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<CoreDump> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not a supported core file");
+
+        let header = read_at(&file, 0, mem::size_of::<Elf>())?;
+        let elf = Elf::parse(&header[..]).map_err(|_| invalid())?;
+        let endian = elf.endian().map_err(|_| invalid())?;
+        if elf.e_type(endian) != ET_CORE || elf.e_machine(endian) != MACHINE {
+            return Err(invalid());
+        }
+        let headers_len = (elf.e_phnum(endian) as usize)
+            .checked_mul(elf.e_phentsize(endian) as usize)
+            .and_then(|len| len.checked_add(elf.e_phoff(endian) as usize))
+            .filter(|&len| len as u64 <= file_len)
+            .ok_or_else(invalid)?;
+        let data = read_at(&file, 0, headers_len)?;
+        let headers = Elf::parse(&data[..])
+            .and_then(|elf| elf.program_headers(endian, &data[..]))
+            .map_err(|_| invalid())?;
+
+        let segments = headers
+            .iter()
+            .filter(|header| header.p_type(endian) == PT_LOAD && header.p_filesz(endian) > 0)
+            .map(|header| {
+                let start = header.p_vaddr(endian) as usize;
+                Segment {
+                    start,
+                    end: start.wrapping_add(header.p_filesz(endian) as usize),
+                    offset: header.p_offset(endian),
+                }
+            })
+            .collect();
+
+        let mut threads = Vec::new();
+        let mut registers = Vec::new();
+        let mut files = Vec::new();
+        for header in headers {
+            if header.p_type(endian) != PT_NOTE {
+                continue;
+            }
+            // Only the notes themselves are read, once it's certain that
+            // they're really in the file rather than wherever its headers say.
+            let offset = header.p_offset(endian);
+            let len = header.p_filesz(endian);
+            match offset.checked_add(len) {
+                Some(end) if end <= file_len => {}
+                _ => return Err(invalid()),
+            }
+            let notes_data = read_at(&file, offset, len as usize)?;
+            let mut header = *header;
+            header.p_offset = U64::new(endian, 0);
+            let mut notes = match header.notes(endian, &notes_data[..]) {
+                Ok(Some(notes)) => notes,
+                _ => return Err(invalid()),
+            };
+            while let Some(note) = notes.next().map_err(|_| invalid())? {
+                if note.name() != b"CORE" {
+                    continue;
+                }
+                match note.n_type(endian) {
+                    NT_PRSTATUS => {
+                        let (id, regs) = parse_prstatus(note.desc()).ok_or_else(invalid)?;
+                        threads.push(id);
+                        registers.push(regs);
+                    }
+                    NT_FILE => files = parse_files(note.desc()).ok_or_else(invalid)?,
+                    _ => {}
+                }
+            }
+        }
+
+        let memory = CoreMemory {
+            file,
+            segments,
+            files,
+        };
+        let modules = {
+            let mappings = memory
+                .files
+                .iter()
+                .filter(|file| file.offset == 0)
+                .map(|file| (file.start, file.end, file.path.as_os_str()))
+                .collect::<Vec<_>>();
+            crate::symbolize::mapped_modules(&mappings, |addr, buf| memory.read(addr, buf))
+        };
+        Ok(CoreDump {
+            threads,
+            registers,
+            process: ForeignProcess::new(memory, modules),
+        })
+    }
+
+    /// Returns the operating system's ids of the threads of the process.
+    ///
+    /// The first thread is the one which caused the process to be dumped, for
+    /// example by crashing.
+    pub fn threads(&self) -> &[u32] {
+        &self.threads
+    }
+
+    /// Returns the modules that were loaded into the process, which are what
+    /// its backtraces are symbolized against.
+    pub fn modules(&self) -> &[Module] {
+        self.process.modules()
+    }
+
+    /// Captures the backtrace of the thread `id` of the process, returning
+    /// `None` if it isn't one of `threads`.
+    ///
+    /// The backtrace is resolved before it's returned.
+    pub fn backtrace(&self, id: u32) -> Option<Backtrace> {
+        let index = self.threads.iter().position(|&thread| thread == id)?;
+        Some(self.process.backtrace(self.registers[index].clone()))
+    }
+
+    /// Captures the backtraces of all threads of the process, in the order of
+    /// `threads`.
+    ///
+    /// Core files don't record the names of threads, so the backtraces don't
+    /// have any. They're resolved before they're returned.
+    pub fn backtraces(&self) -> Vec<ThreadBacktrace> {
+        let threads = self
+            .threads
+            .iter()
+            .zip(&self.registers)
+            .map(|(&id, registers)| (id, String::new(), registers.clone()));
+        self.process.backtraces(threads)
+    }
+}
+
+impl Memory for CoreMemory {
+    fn read(&self, addr: usize, buf: &mut [u8]) -> bool {
+        // A read may span several segments, or segments as well as files.
+        let mut done = 0;
+        while done < buf.len() {
+            match self.read_some(addr.wrapping_add(done), &mut buf[done..]) {
+                Some(len) => done += len,
+                None => return false,
+            }
+        }
+        true
+    }
+}
+
+impl CoreMemory {
+    /// Reads as much of the memory at `addr` into `buf` as is found in one
+    /// place, returning how much that is.
+    fn read_some(&self, addr: usize, buf: &mut [u8]) -> Option<usize> {
+        if let Some(segment) = self
+            .segments
+            .iter()
+            .find(|segment| segment.start <= addr && addr < segment.end)
+        {
+            let len = buf.len().min(segment.end - addr);
+            let offset = segment.offset + (addr - segment.start) as u64;
+            self.file.read_exact_at(&mut buf[..len], offset).ok()?;
+            return Some(len);
+        }
+        let file = self
+            .files
+            .iter()
+            .find(|file| file.start <= addr && addr < file.end)?;
+        let len = buf.len().min(file.end - addr);
+        let offset = file.offset + (addr - file.start) as u64;
+        File::open(&file.path)
+            .ok()?
+            .read_exact_at(&mut buf[..len], offset)
+            .ok()?;
+        Some(len)
+    }
+}
+
+fn read_at(file: &File, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0; len];
+    file.read_exact_at(&mut buf, offset)?;
+    Ok(buf)
+}
+
+/// Parses the descriptor of a `NT_PRSTATUS` note, a `struct elf_prstatus`,
+/// into the id of the thread and its registers.
+fn parse_prstatus(desc: &[u8]) -> Option<(u32, Registers)> {
+    let id = desc.get(PRSTATUS_PID..PRSTATUS_PID + 4)?;
+    let id = u32::from_ne_bytes(id.try_into().ok()?);
+    let regs = desc.get(PRSTATUS_REGS..PRSTATUS_REGS + mem::size_of::<libc::user_regs_struct>())?;
+    let regs = unsafe { ptr::read_unaligned(regs.as_ptr() as *const libc::user_regs_struct) };
+    Some((id, Registers::from_user_regs(&regs)))
+}
+
+/// Parses the descriptor of a `NT_FILE` note: the number of files and the page
+/// size, then the start, end and offset in pages of each mapping, then the
+/// nul-terminated path of each mapped file.
+fn parse_files(desc: &[u8]) -> Option<Vec<MappedFile>> {
+    const WORD: usize = mem::size_of::<usize>();
+    let word = |i: usize| -> Option<usize> {
+        let bytes = desc.get(i.checked_mul(WORD)?..)?.get(..WORD)?;
+        Some(usize::from_ne_bytes(bytes.try_into().ok()?))
+    };
+    let count = word(0)?;
+    let page_size = word(1)?;
+    let mut paths = desc
+        .get(count.checked_mul(3)?.checked_add(2)?.checked_mul(WORD)?..)?
+        .split(|&b| b == 0);
+    (0..count)
+        .map(|i| {
+            let path = paths.next()?;
+            Some(MappedFile {
+                start: word(2 + i * 3)?,
+                end: word(3 + i * 3)?,
+                offset: (word(4 + i * 3)? as u64).checked_mul(page_size as u64)?,
+                path: PathBuf::from(OsString::from_vec(path.to_vec())),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,226 @@
+//! Unwinding and symbolizing the stacks of another process, be it a running one
+//! (`RemoteProcess`) or one that's been dumped into a core file (`CoreDump`).
+//!
+//! Either way all that's needed is a way to read the memory of the process and
+//! the registers of each of its threads. The stacks are unwound using the
+//! `.eh_frame` sections of the objects loaded into the process, copied out of
+//! its memory, and the frames are symbolized against the files those objects
+//! were loaded from.
+
+use crate::backtrace::gimli::{eh_frame_address, AddressSpace, Cursor, Object, Registers};
+use crate::backtrace::FrameImp;
+use crate::{Backtrace, BacktraceFrame, Module, ThreadBacktrace};
+use object::elf::PT_GNU_EH_FRAME;
+use object::read::elf::{FileHeader, ProgramHeader};
+use object::NativeEndian;
+use std::mem;
+use std::prelude::v1::*;
+
+type Elf = object::elf::FileHeader64<NativeEndian>;
+
+// A stack corrupt in just the right way could be unwound forever, so the
+// backtrace of a thread is cut off after this many frames.
+const MAX_FRAMES: usize = 4096;
+
+// Anything larger than this is assumed to be garbage rather than headers worth
+// reading out of the process.
+const MAX_READ: usize = 64 * 1024;
+
+// The most unwind information read for any one module. `.eh_frame` is read up
+// to the end of its segment, which may well hold more than just it, so it's cut
+// off here rather than skipped.
+const MAX_UNWIND_READ: usize = 64 * 1024 * 1024;
+
+/// The memory of another process.
+pub(crate) trait Memory {
+    /// Reads the memory at `addr` into `buf`, returning whether all of it could
+    /// be read.
+    fn read(&self, addr: usize, buf: &mut [u8]) -> bool;
+}
+
+/// Another process along with the modules loaded into it.
+pub(crate) struct ForeignProcess<M> {
+    memory: M,
+    modules: Vec<Module>,
+    objects: Vec<ForeignObject>,
+}
+
+/// The unwind information of one module of the process, copied out of its
+/// memory.
+struct ForeignObject {
+    module: usize,
+    eh_frame_hdr: Vec<u8>,
+    eh_frame_hdr_address: usize,
+    eh_frame: Vec<u8>,
+    eh_frame_address: usize,
+}
+
+impl<M: Memory> ForeignProcess<M> {
+    pub(crate) fn new(memory: M, modules: Vec<Module>) -> ForeignProcess<M> {
+        let objects = (0..modules.len())
+            .filter_map(|i| ForeignObject::load(&memory, &modules, i))
+            .collect();
+        ForeignProcess {
+            memory,
+            modules,
+            objects,
+        }
+    }
+
+    pub(crate) fn modules(&self) -> &[Module] {
+        &self.modules
+    }
+
+    /// Unwinds the stack of the thread with the registers `registers`, and
+    /// resolves the backtrace.
+    pub(crate) fn backtrace(&self, registers: Registers) -> Backtrace {
+        let frames = self.unwind(registers);
+        let mut backtrace = Backtrace::with_foreign_modules(frames, &self.modules);
+        backtrace.resolve_with_modules(&self.modules);
+        backtrace
+    }
+
+    /// Unwinds the stacks of `threads`, given as their ids, names and
+    /// registers, and resolves the backtraces.
+    pub(crate) fn backtraces(
+        &self,
+        threads: impl IntoIterator<Item = (u32, String, Registers)>,
+    ) -> Vec<ThreadBacktrace> {
+        // Resolve everything in one go so the debuginfo of each module is only
+        // loaded once, rather than once per thread.
+        let mut threads_len = Vec::new();
+        let mut all = Vec::new();
+        for (id, name, registers) in threads {
+            let frames = self.unwind(registers);
+            threads_len.push((id, name, frames.len()));
+            all.extend(frames);
+        }
+        let mut all = Backtrace::with_foreign_modules(all, &self.modules);
+        all.resolve_with_modules(&self.modules);
+
+        let mut all: Vec<BacktraceFrame> = all.into();
+        let mut ret = Vec::new();
+        for (id, name, len) in threads_len.into_iter().rev() {
+            let frames = all.split_off(all.len() - len);
+            ret.push(ThreadBacktrace::new(id, &name, Backtrace::from(frames)));
+        }
+        ret.reverse();
+        ret
+    }
+
+    fn unwind(&self, registers: Registers) -> Vec<BacktraceFrame> {
+        let mut cursor = Cursor::new(self, registers);
+        let mut frames = Vec::new();
+        while let Some(frame) = unsafe { cursor.next() } {
+            frames.push(BacktraceFrame::from(crate::Frame {
+                inner: FrameImp::from_addresses(
+                    frame.ip(),
+                    frame.sp(),
+                    frame.symbol_address(),
+                    frame.is_return_address(),
+                    frame.cfa(),
+                    frame.registers(),
+                ),
+            }));
+            if frames.len() == MAX_FRAMES {
+                break;
+            }
+        }
+        frames
+    }
+}
+
+impl<M: Memory> AddressSpace for ForeignProcess<M> {
+    unsafe fn read(&self, addr: usize, size: u8) -> Option<usize> {
+        let mut bytes = [0; 8];
+        let bytes = bytes.get_mut(..size as usize)?;
+        if !self.memory.read(addr, bytes) {
+            return None;
+        }
+        Some(match *bytes {
+            [a] => a as usize,
+            [a, b] => u16::from_ne_bytes([a, b]) as usize,
+            [a, b, c, d] => u32::from_ne_bytes([a, b, c, d]) as usize,
+            [a, b, c, d, e, f, g, h] => u64::from_ne_bytes([a, b, c, d, e, f, g, h]) as usize,
+            _ => return None,
+        })
+    }
+
+    unsafe fn find_object(&self, pc: usize) -> Option<Object<'_>> {
+        let object = self
+            .objects
+            .iter()
+            .find(|object| self.modules[object.module].contains(pc))?;
+        Some(Object {
+            eh_frame_hdr: &object.eh_frame_hdr,
+            eh_frame_hdr_address: object.eh_frame_hdr_address,
+            eh_frame: &object.eh_frame,
+            eh_frame_address: object.eh_frame_address,
+        })
+    }
+}
+
+impl ForeignObject {
+    fn load(memory: &impl Memory, modules: &[Module], index: usize) -> Option<ForeignObject> {
+        let module = &modules[index];
+        let read_vec = |addr: usize, len: usize| {
+            if len > MAX_UNWIND_READ {
+                return None;
+            }
+            let mut buf = vec![0; len];
+            if memory.read(addr, &mut buf) {
+                Some(buf)
+            } else {
+                None
+            }
+        };
+
+        // The program headers are right behind the ELF header at the start of
+        // the module, which is how they were found in the first place.
+        let base = module.base_address();
+        let header = read_vec(base, mem::size_of::<Elf>())?;
+        let elf = Elf::parse(&header[..]).ok()?;
+        let endian = elf.endian().ok()?;
+        let headers_len = (elf.e_phnum(endian) as usize)
+            .checked_mul(elf.e_phentsize(endian) as usize)?
+            .checked_add(elf.e_phoff(endian) as usize)?;
+        if headers_len > MAX_READ {
+            return None;
+        }
+        let data = read_vec(base, headers_len)?;
+        let headers = Elf::parse(&data[..])
+            .ok()?
+            .program_headers(endian, &data[..])
+            .ok()?;
+        let hdr = headers
+            .iter()
+            .find(|header| header.p_type(endian) == PT_GNU_EH_FRAME)?;
+
+        let eh_frame_hdr_address = module.bias().wrapping_add(hdr.p_vaddr(endian) as usize);
+        let eh_frame_hdr = read_vec(eh_frame_hdr_address, hdr.p_memsz(endian) as usize)?;
+        let eh_frame_address = eh_frame_address(&eh_frame_hdr, eh_frame_hdr_address)?;
+        // `.eh_frame` is placed next to `.eh_frame_hdr`, and the segment the
+        // two are in is as far as it can extend.
+        let segment_end = module
+            .segments()
+            .iter()
+            .map(|segment| {
+                let start = segment
+                    .stated_virtual_memory_address()
+                    .wrapping_add(module.bias());
+                (start, start.wrapping_add(segment.size()))
+            })
+            .find(|&(start, end)| start <= eh_frame_address && eh_frame_address < end)?
+            .1;
+        let eh_frame_len = (segment_end - eh_frame_address).min(MAX_UNWIND_READ);
+        let eh_frame = read_vec(eh_frame_address, eh_frame_len)?;
+
+        Some(ForeignObject {
+            module: index,
+            eh_frame_hdr,
+            eh_frame_hdr_address,
+            eh_frame,
+            eh_frame_address,
+        })
+    }
+}
@@ -110,7 +110,17 @@ extern crate std;
 #[allow(unused_extern_crates)]
 extern crate alloc;
 
-pub use self::backtrace::{trace_unsynchronized, Frame};
+#[cfg(feature = "frame-pointers")]
+pub use self::backtrace::set_stack_limits_hook;
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+    not(miri),
+))]
+pub use self::backtrace::trace_from_context_unsynchronized;
+#[cfg(not(windows))]
+pub use self::backtrace::trace_into;
+pub use self::backtrace::{trace_unsynchronized, Frame, RawFrame};
 mod backtrace;
 
 pub use self::symbolize::resolve_frame_unsynchronized;
@@ -122,6 +132,10 @@ mod types;
 
 #[cfg(feature = "std")]
 pub use self::symbolize::clear_symbol_cache;
+#[cfg(feature = "std")]
+pub use self::symbolize::{set_symbol_cache_capacity, symbol_cache_stats};
+#[cfg(feature = "std")]
+pub use self::symbolize::{SymbolCacheCapacity, SymbolCacheStats};
 
 mod print;
 pub use print::{BacktraceFmt, BacktraceFrameFmt, PrintFmt};
@@ -129,9 +143,66 @@ pub use print::{BacktraceFmt, BacktraceFrameFmt, PrintFmt};
 cfg_if::cfg_if! {
     if #[cfg(feature = "std")] {
         pub use self::backtrace::trace;
-        pub use self::symbolize::{resolve, resolve_frame};
-        pub use self::capture::{Backtrace, BacktraceFrame, BacktraceSymbol};
+        #[cfg(all(
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64"),
+            not(miri),
+        ))]
+        pub use self::backtrace::trace_from_context;
+        pub use self::symbolize::{modules, resolve, resolve_batch, resolve_frame, Symbolizer};
+        pub use self::symbolize::{set_debug_dirs, set_diagnostics_hook, Diagnostic};
+        pub use self::capture::{Backtrace, BacktraceBuilder, BacktraceFrame};
+        pub use self::capture::{BacktraceModule, BacktraceSymbol};
         mod capture;
+        pub use self::module::{Module, ModuleSegment};
+        mod module;
+        #[cfg(all(
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64"),
+            not(miri),
+        ))]
+        pub use self::threads::{thread_backtrace, thread_backtraces, ThreadBacktrace};
+        #[cfg(all(
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64"),
+            not(miri),
+        ))]
+        mod threads;
+        #[cfg(all(
+            feature = "ptrace",
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64"),
+            not(miri),
+        ))]
+        pub use self::remote::RemoteProcess;
+        #[cfg(all(
+            feature = "ptrace",
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64"),
+            not(miri),
+        ))]
+        mod remote;
+        #[cfg(all(
+            feature = "coredump",
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64"),
+            not(miri),
+        ))]
+        pub use self::coredump::CoreDump;
+        #[cfg(all(
+            feature = "coredump",
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64"),
+            not(miri),
+        ))]
+        mod coredump;
+        #[cfg(all(
+            any(feature = "ptrace", feature = "coredump"),
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64"),
+            not(miri),
+        ))]
+        mod foreign;
     }
 }
 
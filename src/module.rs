@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+use std::prelude::v1::*;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Description of an executable image (the main executable or a shared
+/// library) loaded into a process.
+///
+/// A list of modules describes where each image lived in the address space of
+/// the process it was recorded in. Paired with an unresolved `Backtrace` from
+/// that process, it allows symbolicating the backtrace at a later time, or in
+/// a different process altogether, through `Backtrace::resolve_with_modules`.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize-rustc", derive(RustcDecodable, RustcEncodable))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Module {
+    path: PathBuf,
+    build_id: Option<Vec<u8>>,
+    bias: usize,
+    segments: Vec<ModuleSegment>,
+}
+
+/// One segment of a `Module` which is mapped into memory.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize-rustc", derive(RustcDecodable, RustcEncodable))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ModuleSegment {
+    stated_virtual_memory_address: usize,
+    len: usize,
+}
+
+impl Module {
+    /// Creates a new description of a module.
+    ///
+    /// The `path` is where the image's file can be found by whoever ends up
+    /// symbolicating against this module, and `build_id` is the GNU build ID
+    /// (or Mach-O UUID) of the image, if known. When a build ID is given then
+    /// debuginfo is only read from files carrying the same ID.
+    ///
+    /// The `bias` is the difference between where the image was actually
+    /// loaded and the addresses stated in the file itself, and `segments`
+    /// lists the ranges of the image, as stated in the file, which were
+    /// mapped into memory.
+    pub fn new(
+        path: PathBuf,
+        build_id: Option<Vec<u8>>,
+        bias: usize,
+        segments: Vec<ModuleSegment>,
+    ) -> Module {
+        Module {
+            path,
+            build_id,
+            bias,
+            segments,
+        }
+    }
+
+    /// Returns the path of the file backing this module.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the GNU build ID (or Mach-O UUID, or the GUID and age of the
+    /// PDB of a PE image) of this module, if known.
+    pub fn build_id(&self) -> Option<&[u8]> {
+        self.build_id.as_ref().map(|id| &id[..])
+    }
+
+    /// Returns the bias of this module, which is added to each segment's
+    /// stated address to get the address it was loaded at.
+    pub fn bias(&self) -> usize {
+        self.bias
+    }
+
+    /// Returns the segments of this module which were mapped into memory.
+    pub fn segments(&self) -> &[ModuleSegment] {
+        &self.segments
+    }
+
+    /// Returns the lowest address at which any of this module's segments
+    /// were loaded, which is typically where its file header is mapped.
+    pub fn base_address(&self) -> usize {
+        self.segments
+            .iter()
+            .filter(|segment| segment.len > 0)
+            .map(|segment| segment.stated_virtual_memory_address)
+            .min()
+            .unwrap_or(0)
+            .wrapping_add(self.bias)
+    }
+
+    /// Returns whether `addr` falls within one of the segments of this module
+    /// as loaded.
+    #[allow(dead_code)]
+    pub(crate) fn contains(&self, addr: usize) -> bool {
+        self.segments.iter().any(|segment| {
+            let start = segment
+                .stated_virtual_memory_address
+                .wrapping_add(self.bias);
+            start <= addr && addr < start.wrapping_add(segment.len)
+        })
+    }
+}
+
+impl ModuleSegment {
+    /// Creates a new segment spanning `len` bytes from the address
+    /// `stated_virtual_memory_address`, as stated in the module's file.
+    pub fn new(stated_virtual_memory_address: usize, len: usize) -> ModuleSegment {
+        ModuleSegment {
+            stated_virtual_memory_address,
+            len,
+        }
+    }
+
+    /// Returns the address of this segment as stated in the module's file.
+    ///
+    /// This is not where the segment was loaded, rather this address plus the
+    /// module's `bias` is.
+    pub fn stated_virtual_memory_address(&self) -> usize {
+        self.stated_virtual_memory_address
+    }
+
+    /// Returns the size of this segment in memory.
+    pub fn size(&self) -> usize {
+        self.len
+    }
+}
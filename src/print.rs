@@ -29,10 +29,26 @@ pub enum PrintFmt {
     Short,
     /// Prints a backtrace that contains all possible information
     Full,
+    /// Prints the same as `Full`, with the name of each function followed by
+    /// the offset of the frame's instruction pointer into it, like
+    /// `function+0x1a`, to line the backtrace up with disassembly.
+    ///
+    /// Offsets are only printed for functions which weren't inlined and where
+    /// it's known at which address they start, see `Symbol::offset`.
+    FullWithOffsets,
     #[doc(hidden)]
     __Nonexhaustive,
 }
 
+impl PrintFmt {
+    fn is_full(self) -> bool {
+        match self {
+            PrintFmt::Full | PrintFmt::FullWithOffsets => true,
+            PrintFmt::Short | PrintFmt::__Nonexhaustive => false,
+        }
+    }
+}
+
 impl<'a, 'b> BacktraceFmt<'a, 'b> {
     /// Create a new `BacktraceFmt` which will write output to the provided
     /// `fmt`.
@@ -75,6 +91,7 @@ impl<'a, 'b> BacktraceFmt<'a, 'b> {
         BacktraceFrameFmt {
             fmt: self,
             symbol_index: 0,
+            label_inlined: false,
         }
     }
 
@@ -94,9 +111,21 @@ impl<'a, 'b> BacktraceFmt<'a, 'b> {
 pub struct BacktraceFrameFmt<'fmt, 'a, 'b> {
     fmt: &'fmt mut BacktraceFmt<'a, 'b>,
     symbol_index: usize,
+    label_inlined: bool,
 }
 
 impl BacktraceFrameFmt<'_, '_, '_> {
+    /// Sets whether symbols of functions which were inlined into their caller
+    /// are labelled with ` (inlined)` after their name, the way `perf` and
+    /// `llvm-symbolizer` do. This is off by default.
+    ///
+    /// Only symbols printed through `backtrace_frame`, `backtrace_symbol` and
+    /// `symbol` are known to be inlined or not, see `Symbol::is_inlined`.
+    pub fn label_inlined(&mut self, label: bool) -> &mut Self {
+        self.label_inlined = label;
+        self
+    }
+
     /// Prints a `BacktraceFrame` with this frame formatter.
     ///
     /// This will recursively print all `BacktraceSymbol` instances within the
@@ -130,7 +159,7 @@ impl BacktraceFrameFmt<'_, '_, '_> {
         frame: &BacktraceFrame,
         symbol: &BacktraceSymbol,
     ) -> fmt::Result {
-        self.print_raw_with_column(
+        self.print_raw_inner(
             frame.ip(),
             symbol.name(),
             // TODO: this isn't great that we don't end up printing anything
@@ -141,6 +170,10 @@ impl BacktraceFrameFmt<'_, '_, '_> {
                 .and_then(|p| Some(BytesOrWideString::Bytes(p.to_str()?.as_bytes()))),
             symbol.lineno(),
             symbol.colno(),
+            SymbolDetails {
+                inlined: symbol.is_inlined(),
+                offset: symbol.offset(),
+            },
         )?;
         Ok(())
     }
@@ -148,12 +181,16 @@ impl BacktraceFrameFmt<'_, '_, '_> {
     /// Prints a raw traced `Frame` and `Symbol`, typically from within the raw
     /// callbacks of this crate.
     pub fn symbol(&mut self, frame: &Frame, symbol: &super::Symbol) -> fmt::Result {
-        self.print_raw_with_column(
+        self.print_raw_inner(
             frame.ip(),
             symbol.name(),
             symbol.filename_raw(),
             symbol.lineno(),
             symbol.colno(),
+            SymbolDetails {
+                inlined: symbol.is_inlined(),
+                offset: symbol.offset(),
+            },
         )?;
         Ok(())
     }
@@ -185,6 +222,19 @@ impl BacktraceFrameFmt<'_, '_, '_> {
         filename: Option<BytesOrWideString<'_>>,
         lineno: Option<u32>,
         colno: Option<u32>,
+    ) -> fmt::Result {
+        let details = SymbolDetails::default();
+        self.print_raw_inner(frame_ip, symbol_name, filename, lineno, colno, details)
+    }
+
+    fn print_raw_inner(
+        &mut self,
+        frame_ip: *mut c_void,
+        symbol_name: Option<SymbolName<'_>>,
+        filename: Option<BytesOrWideString<'_>>,
+        lineno: Option<u32>,
+        colno: Option<u32>,
+        details: SymbolDetails,
     ) -> fmt::Result {
         // Fuchsia is unable to symbolize within a process so it has a special
         // format which can be used to symbolize later. Print that instead of
@@ -192,7 +242,7 @@ impl BacktraceFrameFmt<'_, '_, '_> {
         if cfg!(target_os = "fuchsia") {
             self.print_raw_fuchsia(frame_ip)?;
         } else {
-            self.print_raw_generic(frame_ip, symbol_name, filename, lineno, colno)?;
+            self.print_raw_generic(frame_ip, symbol_name, filename, lineno, colno, details)?;
         }
         self.symbol_index += 1;
         Ok(())
@@ -206,6 +256,7 @@ impl BacktraceFrameFmt<'_, '_, '_> {
         filename: Option<BytesOrWideString<'_>>,
         lineno: Option<u32>,
         colno: Option<u32>,
+        details: SymbolDetails,
     ) -> fmt::Result {
         // No need to print "null" frames, it basically just means that the
         // system backtrace was a bit eager to trace back super far.
@@ -215,6 +266,13 @@ impl BacktraceFrameFmt<'_, '_, '_> {
             }
         }
 
+        // Offsets are of no use for inlined functions as they're into the
+        // function they were inlined into.
+        let offset = match self.fmt.format {
+            PrintFmt::FullWithOffsets if !details.inlined => details.offset,
+            _ => None,
+        };
+
         // To reduce TCB size in Sgx enclave, we do not want to implement symbol
         // resolution functionality.  Rather, we can print the offset of the
         // address here, which could be later mapped to correct function.
@@ -229,12 +287,12 @@ impl BacktraceFrameFmt<'_, '_, '_> {
         // though we just print appropriate whitespace.
         if self.symbol_index == 0 {
             write!(self.fmt.fmt, "{:4}: ", self.fmt.frame_index)?;
-            if let PrintFmt::Full = self.fmt.format {
+            if self.fmt.format.is_full() {
                 write!(self.fmt.fmt, "{:1$?} - ", frame_ip, HEX_WIDTH)?;
             }
         } else {
             write!(self.fmt.fmt, "      ")?;
-            if let PrintFmt::Full = self.fmt.format {
+            if self.fmt.format.is_full() {
                 write!(self.fmt.fmt, "{:1$}", "", HEX_WIDTH + 3)?;
             }
         }
@@ -244,9 +302,17 @@ impl BacktraceFrameFmt<'_, '_, '_> {
         // symbols which don't have a name,
         match (symbol_name, &self.fmt.format) {
             (Some(name), PrintFmt::Short) => write!(self.fmt.fmt, "{:#}", name)?,
-            (Some(name), PrintFmt::Full) => write!(self.fmt.fmt, "{}", name)?,
+            (Some(name), PrintFmt::Full) | (Some(name), PrintFmt::FullWithOffsets) => {
+                write!(self.fmt.fmt, "{}", name)?
+            }
             (None, _) | (_, PrintFmt::__Nonexhaustive) => write!(self.fmt.fmt, "<unknown>")?,
         }
+        if let Some(offset) = offset {
+            write!(self.fmt.fmt, "+{:#x}", offset)?;
+        }
+        if details.inlined && self.label_inlined {
+            self.fmt.fmt.write_str(" (inlined)")?;
+        }
         self.fmt.fmt.write_str("\n")?;
 
         // And last up, print out the filename/line number if they're available.
@@ -265,7 +331,7 @@ impl BacktraceFrameFmt<'_, '_, '_> {
     ) -> fmt::Result {
         // Filename/line are printed on lines under the symbol name, so print
         // some appropriate whitespace to sort of right-align ourselves.
-        if let PrintFmt::Full = self.fmt.format {
+        if self.fmt.format.is_full() {
             write!(self.fmt.fmt, "{:1$}", "", HEX_WIDTH)?;
         }
         write!(self.fmt.fmt, "             at ")?;
@@ -295,6 +361,15 @@ impl BacktraceFrameFmt<'_, '_, '_> {
     }
 }
 
+/// What's known about a symbol being printed besides what's passed to the
+/// `print_raw` methods.
+#[derive(Default)]
+struct SymbolDetails {
+    inlined: bool,
+    /// The offset of the instruction pointer into the function, if known.
+    offset: Option<usize>,
+}
+
 impl Drop for BacktraceFrameFmt<'_, '_, '_> {
     fn drop(&mut self) {
         self.fmt.frame_index += 1;
@@ -0,0 +1,228 @@
+//! Capturing the backtraces of the threads of another process.
+//!
+//! The process is attached to with `ptrace`, which stops all of its threads
+//! and gives access to their registers. Its stacks are then unwound using the
+//! `.eh_frame` sections of the objects loaded into it, read straight out of its
+//! memory, and the frames are symbolized against the files those objects were
+//! loaded from, as found through `/proc/<pid>/maps`.
+
+use crate::backtrace::gimli::Registers;
+use crate::foreign::{ForeignProcess, Memory};
+use crate::{Backtrace, Module, ThreadBacktrace};
+use std::ffi::c_void;
+use std::fs;
+use std::io;
+use std::mem;
+use std::prelude::v1::*;
+use std::ptr;
+
+/// Another process, attached to with `ptrace` to capture the backtraces of its
+/// threads.
+///
+/// All threads of the process are stopped while it's attached to, and resume
+/// where they left off once this is dropped. Attaching needs the same
+/// permissions as attaching a debugger would, which by default on many
+/// distributions means the process has to be a child of the current one.
+///
+/// # Required features
+///
+/// This requires the `ptrace` feature of the `backtrace` crate to be enabled,
+/// and it's only available on Linux on x86_64 and aarch64.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> {
+/// let pid = 1234;
+/// let process = backtrace::RemoteProcess::attach(pid)?;
+/// for thread in process.backtraces() {
+///     println!("{:?}", thread);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct RemoteProcess {
+    pid: u32,
+    threads: Vec<u32>,
+    // The signal each thread was about to receive when it was stopped, which
+    // has to be delivered when it's resumed.
+    pending_signals: Vec<libc::c_int>,
+    process: ForeignProcess<ProcessMemory>,
+}
+
+/// The memory of the process with the contained id.
+struct ProcessMemory(u32);
+
+impl RemoteProcess {
+    /// Attaches to the process `pid`, stopping all of its threads.
+    ///
+    /// Fails if there's no such process or the current one isn't allowed to
+    /// trace it, or if it's already being traced.
+    pub fn attach(pid: u32) -> io::Result<RemoteProcess> {
+        let mut process = RemoteProcess {
+            pid,
+            threads: Vec::new(),
+            pending_signals: Vec::new(),
+            process: ForeignProcess::new(ProcessMemory(pid), Vec::new()),
+        };
+
+        // Threads may be spawned while others are being stopped, so keep
+        // looking until no new ones show up.
+        let mut seen = Vec::new();
+        loop {
+            let ids = fs::read_dir(format!("/proc/{}/task", pid))?
+                .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                .filter(|id| !seen.contains(id))
+                .collect::<Vec<u32>>();
+            if ids.is_empty() {
+                break;
+            }
+            for id in ids {
+                seen.push(id);
+                match seize(id) {
+                    Ok(Some(signal)) => {
+                        process.threads.push(id);
+                        process.pending_signals.push(signal);
+                    }
+                    // The thread exited in the meantime.
+                    Ok(None) => {}
+                    Err(ref e) if e.raw_os_error() == Some(libc::ESRCH) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        if process.threads.is_empty() {
+            return Err(io::Error::from_raw_os_error(libc::ESRCH));
+        }
+
+        let memory = ProcessMemory(pid);
+        let modules = crate::symbolize::remote_modules(pid, |addr, buf| memory.read(addr, buf));
+        process.process = ForeignProcess::new(memory, modules);
+        Ok(process)
+    }
+
+    /// Returns the id of the process.
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Returns the operating system's ids of the threads of the process.
+    pub fn threads(&self) -> &[u32] {
+        &self.threads
+    }
+
+    /// Returns the modules loaded into the process, which are what its
+    /// backtraces are symbolized against.
+    pub fn modules(&self) -> &[Module] {
+        self.process.modules()
+    }
+
+    /// Captures the backtrace of the thread `id` of the process, returning
+    /// `None` if it isn't one of `threads`.
+    ///
+    /// The backtrace is resolved before it's returned.
+    pub fn backtrace(&self, id: u32) -> Option<Backtrace> {
+        if !self.threads.contains(&id) {
+            return None;
+        }
+        Some(self.process.backtrace(registers(id).ok()?))
+    }
+
+    /// Captures the backtraces of all threads of the process.
+    ///
+    /// The backtraces are resolved before they're returned.
+    pub fn backtraces(&self) -> Vec<ThreadBacktrace> {
+        let threads = self.threads.iter().filter_map(|&id| {
+            let comm = fs::read_to_string(format!("/proc/{}/task/{}/comm", self.pid, id))
+                .unwrap_or_default();
+            Some((id, comm, registers(id).ok()?))
+        });
+        self.process.backtraces(threads)
+    }
+}
+
+impl Drop for RemoteProcess {
+    fn drop(&mut self) {
+        for (&id, &signal) in self.threads.iter().zip(&self.pending_signals) {
+            unsafe {
+                libc::ptrace(
+                    libc::PTRACE_DETACH,
+                    id as libc::pid_t,
+                    ptr::null_mut::<c_void>(),
+                    signal as usize as *mut c_void,
+                );
+            }
+        }
+    }
+}
+
+impl Memory for ProcessMemory {
+    fn read(&self, addr: usize, buf: &mut [u8]) -> bool {
+        let local = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut c_void,
+            iov_len: buf.len(),
+        };
+        let remote = libc::iovec {
+            iov_base: addr as *mut c_void,
+            iov_len: buf.len(),
+        };
+        let read =
+            unsafe { libc::process_vm_readv(self.0 as libc::pid_t, &local, 1, &remote, 1, 0) };
+        read >= 0 && read as usize == buf.len()
+    }
+}
+
+/// Attaches to the thread `id` and waits for it to stop, returning the signal
+/// it was about to receive (or zero), or `None` if it exited instead.
+fn seize(id: u32) -> io::Result<Option<libc::c_int>> {
+    let id = id as libc::pid_t;
+    let null = ptr::null_mut::<c_void>();
+    unsafe {
+        if libc::ptrace(libc::PTRACE_SEIZE, id, null, null) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if libc::ptrace(libc::PTRACE_INTERRUPT, id, null, null) < 0 {
+            let err = io::Error::last_os_error();
+            libc::ptrace(libc::PTRACE_DETACH, id, null, null);
+            return Err(err);
+        }
+        let mut status = 0;
+        if libc::waitpid(id, &mut status, libc::__WALL) < 0 {
+            let err = io::Error::last_os_error();
+            libc::ptrace(libc::PTRACE_DETACH, id, null, null);
+            return Err(err);
+        }
+        if !libc::WIFSTOPPED(status) {
+            return Ok(None);
+        }
+        // The thread may have stopped for a signal before getting to the
+        // interrupt, in which case the signal is suppressed unless it's passed
+        // on when detaching. Stops for the interrupt itself are reported as a
+        // ptrace event instead.
+        if status >> 16 == 0 {
+            Ok(Some(libc::WSTOPSIG(status)))
+        } else {
+            Ok(Some(0))
+        }
+    }
+}
+
+fn registers(id: u32) -> io::Result<Registers> {
+    unsafe {
+        let mut user: libc::user_regs_struct = mem::zeroed();
+        let mut iov = libc::iovec {
+            iov_base: &mut user as *mut libc::user_regs_struct as *mut c_void,
+            iov_len: mem::size_of::<libc::user_regs_struct>(),
+        };
+        let ret = libc::ptrace(
+            libc::PTRACE_GETREGSET,
+            id as libc::pid_t,
+            object::elf::NT_PRSTATUS as usize as *mut c_void,
+            &mut iov as *mut libc::iovec as *mut c_void,
+        );
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Registers::from_user_regs(&user))
+    }
+}
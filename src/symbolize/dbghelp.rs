@@ -29,6 +29,7 @@ use core::slice;
 pub struct Symbol<'a> {
     name: *const [u8],
     addr: *mut c_void,
+    size: Option<usize>,
     line: Option<u32>,
     filename: Option<*const [u16]>,
     #[cfg(feature = "std")]
@@ -47,6 +48,14 @@ impl Symbol<'_> {
         Some(self.addr as *mut _)
     }
 
+    pub fn size(&self) -> Option<usize> {
+        self.size
+    }
+
+    pub fn offset(&self) -> Option<usize> {
+        None
+    }
+
     pub fn filename_raw(&self) -> Option<BytesOrWideString<'_>> {
         self.filename
             .map(|slice| unsafe { BytesOrWideString::Wide(&*slice) })
@@ -66,6 +75,27 @@ impl Symbol<'_> {
 
         self._filename_cache.as_ref().map(Path::new)
     }
+
+    pub fn is_inlined(&self) -> bool {
+        false
+    }
+
+    pub fn call_filename_raw(&self) -> Option<BytesOrWideString<'_>> {
+        None
+    }
+
+    #[cfg(feature = "std")]
+    pub fn call_filename(&self) -> Option<&::std::path::Path> {
+        None
+    }
+
+    pub fn call_lineno(&self) -> Option<u32> {
+        None
+    }
+
+    pub fn call_colno(&self) -> Option<u32> {
+        None
+    }
 }
 
 #[repr(C, align(8))]
@@ -79,7 +109,9 @@ pub unsafe fn resolve(what: ResolveWhat<'_>, cb: &mut dyn FnMut(&super::Symbol))
     };
 
     match what {
-        ResolveWhat::Address(_) => resolve_without_inline(&dbghelp, what.address_or_ip(), cb),
+        ResolveWhat::Address(_) | ResolveWhat::ExactAddress(_) => {
+            resolve_without_inline(&dbghelp, what.address_or_ip(), cb)
+        }
         ResolveWhat::Frame(frame) => match &frame.inner.stack_frame {
             StackFrame::New(frame) => resolve_with_inline(&dbghelp, frame, cb),
             StackFrame::Old(_) => resolve_without_inline(&dbghelp, frame.ip(), cb),
@@ -198,6 +230,7 @@ unsafe fn do_resolve(
         inner: Symbol {
             name,
             addr: info.Address as *mut _,
+            size: Some(info.Size as usize).filter(|&size| size != 0),
             line: lineno,
             filename,
             _filename_cache: cache(filename),
@@ -216,3 +249,104 @@ unsafe fn cache(filename: Option<*const [u16]>) -> Option<::std::ffi::OsString>
 unsafe fn cache(_filename: Option<*const [u16]>) {}
 
 pub unsafe fn clear_symbol_cache() {}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_batch(addrs: &[*mut c_void], cb: &mut dyn FnMut(usize, &super::Symbol)) {
+    for (i, &addr) in addrs.iter().enumerate() {
+        resolve(ResolveWhat::Address(addr), &mut |sym| cb(i, sym));
+    }
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_with_modules(
+    _modules: &[crate::Module],
+    _addrs: &[ResolveWhat<'_>],
+    _cb: &mut dyn FnMut(usize, &super::Symbol),
+) {
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn find_modules(addrs: &[*mut c_void], cb: &mut dyn FnMut(usize, &crate::Module)) {
+    let modules = modules();
+    for (i, addr) in addrs.iter().enumerate() {
+        let addr = ResolveWhat::Address(*addr).address_or_ip() as usize;
+        if let Some(module) = modules.iter().find(|m| m.contains(addr)) {
+            cb(i, module);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn modules() -> std::vec::Vec<crate::Module> {
+    let mut ret = std::vec::Vec::new();
+    super::toolhelp::for_each_loaded_image(|me| {
+        if let Some(module) = module(me) {
+            ret.push(module);
+        }
+    });
+    ret
+}
+
+#[cfg(feature = "std")]
+unsafe fn module(me: &MODULEENTRY32W) -> Option<crate::Module> {
+    use std::io::Read;
+    use std::os::windows::ffi::OsStringExt;
+
+    let path = std::path::PathBuf::from(::std::ffi::OsString::from_wide(
+        super::toolhelp::image_path(me),
+    ));
+
+    // As in `gimli/libs_windows.rs` the module is described as if it were
+    // loaded at the "image base" stated in its file, which the loader may
+    // have overwritten in the copy of the headers in memory. Those headers
+    // are all that's needed, and they fit well within the first page.
+    let mut headers = std::vec::Vec::new();
+    std::fs::File::open(&path)
+        .ok()?
+        .take(4096)
+        .read_to_end(&mut headers)
+        .ok()?;
+    let image_base = super::toolhelp::image_base(&headers)?;
+    let base_addr = me.modBaseAddr as usize;
+    Some(crate::Module::new(
+        path,
+        super::toolhelp::codeview_id(me),
+        base_addr.wrapping_sub(image_base),
+        vec![crate::ModuleSegment::new(
+            image_base,
+            me.modBaseSize as usize,
+        )],
+    ))
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn set_symbol_cache_capacity(_capacity: crate::SymbolCacheCapacity) {}
+
+#[cfg(feature = "std")]
+pub unsafe fn symbol_cache_stats() -> crate::SymbolCacheStats {
+    crate::SymbolCacheStats::default()
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn set_debug_dirs(_dirs: std::vec::Vec<std::path::PathBuf>) {}
+
+#[cfg(feature = "std")]
+pub unsafe fn set_diagnostics_hook(_hook: Option<std::boxed::Box<super::DiagnosticsHook>>) {}
+
+#[cfg(feature = "std")]
+pub struct Symbolizer {
+    _private: (),
+}
+
+#[cfg(feature = "std")]
+impl Symbolizer {
+    pub unsafe fn new(_path: &std::path::Path) -> Option<Symbolizer> {
+        None
+    }
+
+    pub fn build_id(&self) -> Option<&[u8]> {
+        None
+    }
+
+    pub fn resolve(&mut self, _addr: u64, _cb: &mut dyn FnMut(&super::Symbol)) {}
+}
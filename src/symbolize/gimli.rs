@@ -5,6 +5,7 @@
 use self::gimli::read::EndianSlice;
 use self::gimli::NativeEndian as Endian;
 use self::mmap::Mmap;
+use self::split_dwarf::SplitDwarf;
 use self::stash::Stash;
 use super::BytesOrWideString;
 use super::ResolveWhat;
@@ -16,7 +17,7 @@ use core::u32;
 use libc::c_void;
 use mystd::ffi::OsString;
 use mystd::fs::File;
-use mystd::path::Path;
+use mystd::path::{Path, PathBuf};
 use mystd::prelude::v1::*;
 
 #[cfg(backtrace_in_libstd)]
@@ -50,6 +51,7 @@ cfg_if::cfg_if! {
     }
 }
 
+mod split_dwarf;
 mod stash;
 
 const MAPPINGS_CACHE_SIZE: usize = 4;
@@ -57,6 +59,9 @@ const MAPPINGS_CACHE_SIZE: usize = 4;
 struct Mapping {
     // 'static lifetime is a lie to hack around lack of support for self-referential structs.
     cx: Context<'static>,
+    /// Approximate number of bytes held by this mapping, i.e. the size of the
+    /// mapped file plus whatever was decompressed or mapped into the stash.
+    size: usize,
     _map: Mmap,
     _stash: Stash,
 }
@@ -96,15 +101,74 @@ impl Mapping {
             // Convert to 'static lifetimes since the symbols should
             // only borrow `map` and `stash` and we're preserving them below.
             cx: unsafe { core::mem::transmute::<Context<'_>, Context<'static>>(cx) },
+            size: data.len() + stash.size(),
             _map: data,
             _stash: stash,
         })
     }
+
+    /// Creates a `Mapping` for the file backing `lib`.
+    ///
+    /// If `lib` records the build ID it was loaded with then the mapping is
+    /// rejected when the file on disk turns out to be a different build, since
+    /// symbolizing against it would silently produce garbage.
+    fn for_library(lib: &Library) -> Option<Mapping> {
+        let mapping = Mapping::new(lib.name.as_ref())?;
+        if let (Some(expected), Some(actual)) = (&lib.build_id, mapping.cx.object.build_id()) {
+            if expected[..] != *actual {
+                return None;
+            }
+        }
+        Some(mapping)
+    }
 }
 
 struct Context<'a> {
     dwarf: addr2line::Context<EndianSlice<'a, Endian>>,
     object: Object<'a>,
+    split: SplitDwarf<'a>,
+    /// The subprograms of each unit which a function has been looked up in so
+    /// far, see `subprograms`, sorted by the offset of the unit.
+    subprograms: Vec<(gimli::UnitSectionOffset, Vec<Subprogram>)>,
+}
+
+/// The address range of a subprogram as `(begin, end, offset)`, where `offset`
+/// is that of its entry within its unit.
+type Subprogram = (u64, u64, gimli::UnitOffset);
+
+/// Returns the address ranges of the subprograms of `unit`, sorted by address.
+fn subprograms<'a>(
+    dwarf: &gimli::Dwarf<EndianSlice<'a, Endian>>,
+    unit: &gimli::Unit<EndianSlice<'a, Endian>>,
+) -> Vec<Subprogram> {
+    let mut subprograms = Vec::new();
+    let mut entries = unit.entries();
+    while let Ok(Some((_, entry))) = entries.next_dfs() {
+        if entry.tag() != gimli::DW_TAG_subprogram {
+            continue;
+        }
+        let mut ranges = match dwarf.die_ranges(unit, entry) {
+            Ok(ranges) => ranges,
+            Err(_) => continue,
+        };
+        while let Ok(Some(range)) = ranges.next() {
+            if range.begin < range.end {
+                subprograms.push((range.begin, range.end, entry.offset()));
+            }
+        }
+    }
+    subprograms.sort_unstable_by_key(|&(begin, _, _)| begin);
+    subprograms
+}
+
+/// Returns the subprogram among `subprograms`, as returned by `subprograms`,
+/// which contains `addr`.
+fn find_subprogram(subprograms: &[Subprogram], addr: u64) -> Option<&Subprogram> {
+    let i = match subprograms.binary_search_by_key(&addr, |&(begin, _, _)| begin) {
+        Ok(i) => i,
+        Err(i) => i.checked_sub(1)?,
+    };
+    subprograms.get(i).filter(|&&(_, end, _)| addr < end)
 }
 
 impl<'data> Context<'data> {
@@ -129,7 +193,12 @@ impl<'data> Context<'data> {
         }
         let dwarf = addr2line::Context::from_dwarf(sections).ok()?;
 
-        Some(Context { dwarf, object })
+        Some(Context {
+            dwarf,
+            object,
+            split: SplitDwarf::new(None),
+            subprograms: Vec::new(),
+        })
     }
 }
 
@@ -161,6 +230,7 @@ cfg_if::cfg_if! {
     if #[cfg(windows)] {
         mod libs_windows;
         use libs_windows::native_libraries;
+        use self::unknown_libraries_generation as libraries_generation;
     } else if #[cfg(any(
         target_os = "macos",
         target_os = "ios",
@@ -169,9 +239,11 @@ cfg_if::cfg_if! {
     ))] {
         mod libs_macos;
         use libs_macos::native_libraries;
+        use self::unknown_libraries_generation as libraries_generation;
     } else if #[cfg(target_os = "illumos")] {
         mod libs_illumos;
         use libs_illumos::native_libraries;
+        use self::unknown_libraries_generation as libraries_generation;
     } else if #[cfg(all(
         any(
             target_os = "linux",
@@ -183,23 +255,39 @@ cfg_if::cfg_if! {
         not(target_env = "uclibc"),
     ))] {
         mod libs_dl_iterate_phdr;
-        use libs_dl_iterate_phdr::native_libraries;
+        use libs_dl_iterate_phdr::{libraries_generation, native_libraries};
         #[path = "gimli/parse_running_mmaps_unix.rs"]
         mod parse_running_mmaps;
+        #[cfg(all(
+            any(feature = "ptrace", feature = "coredump"),
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64"),
+        ))]
+        mod libs_remote;
     } else if #[cfg(target_env = "libnx")] {
         mod libs_libnx;
         use libs_libnx::native_libraries;
+        use self::unknown_libraries_generation as libraries_generation;
     } else if #[cfg(target_os = "haiku")] {
         mod libs_haiku;
         use libs_haiku::native_libraries;
+        use self::unknown_libraries_generation as libraries_generation;
     } else {
         // Everything else should doesn't know how to load native libraries.
         fn native_libraries() -> Vec<Library> {
             Vec::new()
         }
+        use self::unknown_libraries_generation as libraries_generation;
     }
 }
 
+/// Stand-in for `libraries_generation` on platforms which don't keep track of
+/// libraries being loaded and unloaded.
+#[allow(dead_code)]
+fn unknown_libraries_generation() -> Option<u64> {
+    None
+}
+
 #[derive(Default)]
 struct Cache {
     /// All known shared libraries that have been loaded.
@@ -207,18 +295,56 @@ struct Cache {
 
     /// Mappings cache where we retain parsed dwarf information.
     ///
-    /// This list has a fixed capacity for its entire lifetime which never
-    /// increases. The `usize` element of each pair is an index into `libraries`
-    /// above where `usize::max_value()` represents the current executable. The
-    /// `Mapping` is corresponding parsed dwarf information.
+    /// This list is bounded by the global `CacheCapacity`. The `usize` element
+    /// of each pair is an index into `libraries` above where
+    /// `usize::max_value()` represents the current executable. The `Mapping`
+    /// is corresponding parsed dwarf information.
     ///
     /// Note that this is basically an LRU cache and we'll be shifting things
     /// around in here as we symbolize addresses.
     mappings: Vec<(usize, Mapping)>,
+
+    /// Counters of how well the `mappings` cache has been doing.
+    stats: CacheStats,
+
+    /// Whether `libraries` are those loaded into the current process, and
+    /// should thus be kept up to date as libraries are loaded and unloaded.
+    native: bool,
+
+    /// The value of `libraries_generation` when `libraries` was last loaded.
+    generation: Option<u64>,
+
+    /// Whether `generation` has been compared with `libraries_generation`
+    /// during the current `Cache::with_global` borrow. That's done once per
+    /// borrow rather than for every address looked up, as it's not free.
+    generation_checked: bool,
+}
+
+/// Limits on the size of the mappings cache, see `set_symbol_cache_capacity`.
+#[derive(Clone, Copy)]
+struct CacheCapacity {
+    /// Maximum number of mappings.
+    entries: usize,
+    /// Maximum approximate number of bytes held by all mappings together.
+    bytes: usize,
+}
+
+#[derive(Default)]
+struct CacheStats {
+    hits: u64,
+    misses: u64,
+    evictions: u64,
 }
 
 struct Library {
     name: OsString,
+    /// The build ID (or UUID) this library is expected to have, if known. When
+    /// present, debuginfo is only loaded from a file carrying the same ID.
+    build_id: Option<Vec<u8>>,
+    /// The device and inode numbers of the file at `name` when the library
+    /// was found to be loaded, if known. Together with the other fields this
+    /// tells apart different files which were loaded from the same path.
+    file_id: Option<(u64, u64)>,
     /// Segments of this library loaded into memory, and where they're loaded.
     segments: Vec<LibrarySegment>,
     /// The "bias" of this library, typically where it's loaded into memory.
@@ -238,16 +364,344 @@ struct LibrarySegment {
     len: usize,
 }
 
+impl Library {
+    /// Returns whether `other` describes the very same library as `self`, as
+    /// opposed to another library which was loaded at the same address, or
+    /// from the same path, after `self` was unloaded.
+    fn is_same(&self, other: &Library) -> bool {
+        self.same_file(other)
+            && self.bias == other.bias
+            && self.segments.len() == other.segments.len()
+            && self.segments.iter().zip(&other.segments).all(|(a, b)| {
+                a.stated_virtual_memory_address == b.stated_virtual_memory_address && a.len == b.len
+            })
+    }
+
+    /// Returns whether `other` is backed by the same file as `self`, and would
+    /// thus have the same debuginfo, wherever either of them is loaded.
+    fn same_file(&self, other: &Library) -> bool {
+        self.name == other.name && self.build_id == other.build_id && self.file_id == other.file_id
+    }
+}
+
+/// Returns the libraries loaded into the current process, with their
+/// `file_id` filled in where possible.
+fn identified_native_libraries() -> Vec<Library> {
+    let mut libraries = native_libraries();
+    for lib in libraries.iter_mut() {
+        lib.file_id = file_id(Path::new(&lib.name));
+    }
+    libraries
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(unix)] {
+        fn file_id(path: &Path) -> Option<(u64, u64)> {
+            use mystd::os::unix::fs::MetadataExt;
+
+            if path.as_os_str().is_empty() {
+                return None;
+            }
+            let metadata = mystd::fs::metadata(path).ok()?;
+            Some((metadata.dev(), metadata.ino()))
+        }
+    } else {
+        fn file_id(_path: &Path) -> Option<(u64, u64)> {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> From<&'a crate::Module> for Library {
+    fn from(module: &'a crate::Module) -> Library {
+        Library {
+            name: module.path().as_os_str().to_owned(),
+            build_id: module.build_id().map(|id| id.to_vec()),
+            file_id: None,
+            segments: module
+                .segments()
+                .iter()
+                .map(|segment| LibrarySegment {
+                    stated_virtual_memory_address: segment.stated_virtual_memory_address(),
+                    len: segment.size(),
+                })
+                .collect(),
+            bias: module.bias(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> From<&'a Library> for crate::Module {
+    fn from(lib: &'a Library) -> crate::Module {
+        crate::Module::new(
+            lib.name.clone().into(),
+            lib.build_id.clone(),
+            lib.bias,
+            lib.segments
+                .iter()
+                .map(|segment| {
+                    crate::ModuleSegment::new(segment.stated_virtual_memory_address, segment.len)
+                })
+                .collect(),
+        )
+    }
+}
+
 // unsafe because this is required to be externally synchronized
 pub unsafe fn clear_symbol_cache() {
     Cache::with_global(|cache| cache.mappings.clear());
+    #[cfg(feature = "std")]
+    Cache::with_offline(|cache| cache.mappings.clear());
+}
+
+// unsafe because this is required to be externally synchronized
+unsafe fn with_cache_capacity<R>(f: impl FnOnce(&mut CacheCapacity) -> R) -> R {
+    static mut CACHE_CAPACITY: CacheCapacity = CacheCapacity {
+        entries: MAPPINGS_CACHE_SIZE,
+        bytes: usize::max_value(),
+    };
+
+    f(&mut CACHE_CAPACITY)
+}
+
+// unsafe because this is required to be externally synchronized
+#[cfg(feature = "std")]
+pub unsafe fn set_symbol_cache_capacity(capacity: crate::SymbolCacheCapacity) {
+    let capacity = match capacity {
+        crate::SymbolCacheCapacity::Entries(entries) => CacheCapacity {
+            entries,
+            bytes: usize::max_value(),
+        },
+        crate::SymbolCacheCapacity::Bytes(bytes) => CacheCapacity {
+            entries: usize::max_value(),
+            bytes,
+        },
+        crate::SymbolCacheCapacity::__Nonexhaustive => return,
+    };
+    with_cache_capacity(|slot| *slot = capacity);
+    Cache::with_global(|cache| cache.evict(capacity, 0, 0));
+    Cache::with_offline(|cache| cache.evict(capacity, 0, 0));
+}
+
+// unsafe because this is required to be externally synchronized
+#[cfg(feature = "std")]
+pub unsafe fn symbol_cache_stats() -> crate::SymbolCacheStats {
+    let mut stats = crate::SymbolCacheStats::default();
+    let mut add = |cache: &mut Cache| {
+        stats.hits += cache.stats.hits;
+        stats.misses += cache.stats.misses;
+        stats.evictions += cache.stats.evictions;
+        stats.entries += cache.mappings.len();
+        stats.bytes += cache
+            .mappings
+            .iter()
+            .map(|(_, mapping)| mapping.size)
+            .sum::<usize>();
+    };
+    Cache::with_global(&mut add);
+    Cache::with_offline(&mut add);
+    stats
+}
+
+// unsafe because this is required to be externally synchronized
+#[allow(dead_code)]
+unsafe fn with_debug_dirs<R>(f: impl FnOnce(&mut Vec<PathBuf>) -> R) -> R {
+    // Directories configured through `set_debug_dirs`, which are searched for
+    // separate debuginfo before any of the default ones.
+    static mut DEBUG_DIRS: Vec<PathBuf> = Vec::new();
+
+    f(&mut DEBUG_DIRS)
+}
+
+// unsafe because this is required to be externally synchronized
+#[cfg(feature = "std")]
+pub unsafe fn set_debug_dirs(dirs: Vec<PathBuf>) {
+    with_debug_dirs(|slot| *slot = dirs);
+}
+
+// unsafe because this is required to be externally synchronized
+#[cfg(feature = "std")]
+unsafe fn with_diagnostics_hook<R>(
+    f: impl FnOnce(&mut Option<mystd::sync::Arc<super::DiagnosticsHook>>) -> R,
+) -> R {
+    static mut DIAGNOSTICS_HOOK: Option<mystd::sync::Arc<super::DiagnosticsHook>> = None;
+
+    f(&mut DIAGNOSTICS_HOOK)
+}
+
+// unsafe because this is required to be externally synchronized
+#[cfg(feature = "std")]
+pub unsafe fn set_diagnostics_hook(hook: Option<Box<super::DiagnosticsHook>>) {
+    with_diagnostics_hook(|slot| *slot = hook.map(Into::into));
+}
+
+/// A `Diagnostic` which has been reported but not yet passed to the hook.
+#[cfg(feature = "std")]
+enum PendingDiagnostic {
+    DebuglinkCrcMismatch {
+        path: PathBuf,
+        expected: u32,
+        actual: u32,
+    },
+}
+
+// unsafe because this is required to be externally synchronized
+#[cfg(feature = "std")]
+unsafe fn with_pending_diagnostics<R>(f: impl FnOnce(&mut Vec<PendingDiagnostic>) -> R) -> R {
+    static mut PENDING_DIAGNOSTICS: Vec<PendingDiagnostic> = Vec::new();
+
+    f(&mut PENDING_DIAGNOSTICS)
+}
+
+/// Queues `diagnostic` to be reported to the hook installed through
+/// `set_diagnostics_hook` by `report_diagnostics`.
+///
+/// This is only ever called while the global cache is in use, so it's
+/// externally synchronized as well. The hook isn't called right away since it
+/// may well call back into this crate, which would then use the cache again
+/// while it's still borrowed.
+#[cfg(feature = "std")]
+#[allow(dead_code)]
+fn diagnose(diagnostic: crate::Diagnostic<'_>) {
+    let pending = match diagnostic {
+        crate::Diagnostic::DebuglinkCrcMismatch {
+            path,
+            expected,
+            actual,
+        } => PendingDiagnostic::DebuglinkCrcMismatch {
+            path: path.to_path_buf(),
+            expected,
+            actual,
+        },
+        crate::Diagnostic::__Nonexhaustive => return,
+    };
+    unsafe { with_pending_diagnostics(|queue| queue.push(pending)) }
+}
+
+/// Passes the diagnostics queued by `diagnose` to the hook.
+///
+/// Neither the cache nor any other global state is borrowed while the hook
+/// runs, so it's free to resolve symbols or replace itself.
+// unsafe because this is required to be externally synchronized
+#[cfg(feature = "std")]
+unsafe fn report_diagnostics() {
+    loop {
+        let pending = with_pending_diagnostics(|queue| {
+            if queue.is_empty() {
+                None
+            } else {
+                Some(queue.remove(0))
+            }
+        });
+        let pending = match pending {
+            Some(pending) => pending,
+            None => break,
+        };
+        let hook = match with_diagnostics_hook(|hook| hook.clone()) {
+            Some(hook) => hook,
+            None => continue,
+        };
+        match pending {
+            PendingDiagnostic::DebuglinkCrcMismatch {
+                path,
+                expected,
+                actual,
+            } => hook(&crate::Diagnostic::DebuglinkCrcMismatch {
+                path: &path,
+                expected,
+                actual,
+            }),
+        }
+    }
 }
 
 impl Cache {
     fn new() -> Cache {
+        let generation = libraries_generation();
+        Cache {
+            native: true,
+            generation,
+            ..Cache::from_libraries(identified_native_libraries())
+        }
+    }
+
+    /// Creates a cache which symbolizes against `libraries` rather than the
+    /// libraries loaded into the current process.
+    fn from_libraries(libraries: Vec<Library>) -> Cache {
         Cache {
             mappings: Vec::with_capacity(MAPPINGS_CACHE_SIZE),
-            libraries: native_libraries(),
+            libraries,
+            stats: CacheStats::default(),
+            native: false,
+            generation: None,
+            generation_checked: false,
+        }
+    }
+
+    /// Translates `addr` like `avma_to_svma`, first bringing the list of
+    /// libraries up to date if libraries may have been loaded or unloaded
+    /// since it was loaded.
+    ///
+    /// Where the platform keeps track of changes to the set of loaded
+    /// libraries the list is reloaded whenever that changed, as checked at the
+    /// first lookup of each `Cache::with_global` borrow. Otherwise it's
+    /// reloaded only when `addr` isn't within any library we know of, which
+    /// catches libraries loaded since but not those which were unloaded.
+    fn lookup(&mut self, addr: *const u8) -> Option<(usize, *const u8)> {
+        if !self.native {
+            return self.avma_to_svma(addr);
+        }
+        match self.generation {
+            Some(_) => {
+                self.check_generation();
+                self.avma_to_svma(addr)
+            }
+            None => self.avma_to_svma(addr).or_else(|| {
+                self.refresh_libraries();
+                self.avma_to_svma(addr)
+            }),
+        }
+    }
+
+    /// Reloads the list of libraries if `libraries_generation` changed since
+    /// it was loaded, unless that was already checked during this borrow.
+    fn check_generation(&mut self) {
+        if !self.native || self.generation.is_none() || self.generation_checked {
+            return;
+        }
+        self.generation_checked = true;
+        if libraries_generation() != self.generation {
+            self.refresh_libraries();
+        }
+    }
+
+    /// Reloads the list of libraries loaded into the current process.
+    ///
+    /// Mappings are keyed by their library's index, so the ones whose library
+    /// is still loaded are moved over to its new index. The others are stale:
+    /// their library was unloaded, possibly with a different one (or another
+    /// build of the same one) loaded in its place, so they're dropped.
+    fn refresh_libraries(&mut self) {
+        self.generation = libraries_generation();
+        self.replace_libraries(identified_native_libraries(), Library::is_same);
+    }
+
+    /// Replaces the list of libraries with `libraries`, moving the mappings of
+    /// the old libraries over to the first new one which is `same` as it and
+    /// dropping the others.
+    fn replace_libraries(&mut self, libraries: Vec<Library>, same: fn(&Library, &Library) -> bool) {
+        let old = mem::replace(&mut self.libraries, libraries);
+        let mappings = mem::take(&mut self.mappings);
+        for (lib, mapping) in mappings {
+            let old = &old[lib];
+            let new = self.libraries.iter().position(|new| same(new, old));
+            if let Some(new) = new {
+                if self.mappings.iter().all(|&(lib, _)| lib != new) {
+                    self.mappings.push((new, mapping));
+                }
+            }
         }
     }
 
@@ -265,7 +719,28 @@ impl Cache {
         // never happen, and symbolicating backtraces would be ssssllllooooowwww.
         static mut MAPPINGS_CACHE: Option<Cache> = None;
 
-        f(MAPPINGS_CACHE.get_or_insert_with(|| Cache::new()))
+        let cache = MAPPINGS_CACHE.get_or_insert_with(|| Cache::new());
+        cache.generation_checked = false;
+        f(cache);
+
+        // Only now that the cache isn't borrowed anymore can the hook be
+        // called, as it may use the cache itself.
+        #[cfg(feature = "std")]
+        report_diagnostics();
+    }
+
+    // unsafe because this is required to be externally synchronized
+    #[cfg(feature = "std")]
+    unsafe fn with_offline(f: impl FnOnce(&mut Self)) {
+        // The mappings of the modules last symbolicated against through
+        // `resolve_with_modules`, kept apart from those of the libraries loaded
+        // into this process. Mappings only depend on the file backing each
+        // module, so they're reused for the modules of the next call which
+        // are backed by the same file, even if those are loaded elsewhere.
+        static mut OFFLINE_CACHE: Option<Cache> = None;
+
+        f(OFFLINE_CACHE.get_or_insert_with(|| Cache::from_libraries(Vec::new())));
+        report_diagnostics();
     }
 
     fn avma_to_svma(&self, addr: *const u8) -> Option<(usize, *const u8)> {
@@ -302,6 +777,29 @@ impl Cache {
             .next()
     }
 
+    /// Evicts the least recently used mappings until there's room for another
+    /// `entries` mappings of `size` bytes in total within `capacity`.
+    ///
+    /// A new mapping is always let in, even if it exceeds the capacity all by
+    /// itself, since it has to be kept somewhere while it's being used.
+    fn evict(&mut self, capacity: CacheCapacity, entries: usize, size: usize) {
+        let mut bytes = self
+            .mappings
+            .iter()
+            .map(|(_, mapping)| mapping.size)
+            .sum::<usize>();
+        while let Some((_, last)) = self.mappings.last() {
+            let entries_fit = self.mappings.len() + entries <= capacity.entries;
+            let bytes_fit = bytes.saturating_add(size) <= capacity.bytes;
+            if entries_fit && bytes_fit {
+                break;
+            }
+            bytes -= last.size;
+            self.mappings.pop();
+            self.stats.evictions += 1;
+        }
+    }
+
     fn mapping_for_lib<'a>(&'a mut self, lib: usize) -> Option<&'a mut Context<'a>> {
         let idx = self.mappings.iter().position(|(idx, _)| *idx == lib);
 
@@ -310,6 +808,7 @@ impl Cache {
 
         if let Some(idx) = idx {
             // When the mapping is already in the cache, move it to the front.
+            self.stats.hits += 1;
             if idx != 0 {
                 let entry = self.mappings.remove(idx);
                 self.mappings.insert(0, entry);
@@ -317,14 +816,12 @@ impl Cache {
         } else {
             // When the mapping is not in the cache, create a new mapping,
             // insert it into the front of the cache, and evict the oldest cache
-            // entry if necessary.
-            let name = &self.libraries[lib].name;
-            let mapping = Mapping::new(name.as_ref())?;
-
-            if self.mappings.len() == MAPPINGS_CACHE_SIZE {
-                self.mappings.pop();
-            }
+            // entries as necessary to make room for it.
+            self.stats.misses += 1;
+            let mapping = Mapping::for_library(&self.libraries[lib])?;
 
+            let capacity = unsafe { with_cache_capacity(|capacity| *capacity) };
+            self.evict(capacity, 1, mapping.size);
             self.mappings.insert(0, (lib, mapping));
         }
 
@@ -336,65 +833,411 @@ impl Cache {
 }
 
 pub unsafe fn resolve(what: ResolveWhat<'_>, cb: &mut dyn FnMut(&super::Symbol)) {
-    let addr = what.address_or_ip();
-    let mut call = |sym: Symbol<'_>| {
-        // Extend the lifetime of `sym` to `'static` since we are unfortunately
-        // required to here, but it's only ever going out as a reference so no
-        // reference to it should be persisted beyond this frame anyway.
-        let sym = mem::transmute::<Symbol<'_>, Symbol<'static>>(sym);
-        (cb)(&super::Symbol { inner: sym });
-    };
+    Cache::with_global(|cache| cache.resolve(what, cb));
+}
 
+// unsafe because this is required to be externally synchronized
+#[cfg(feature = "std")]
+pub unsafe fn resolve_batch(addrs: &[*mut c_void], cb: &mut dyn FnMut(usize, &super::Symbol)) {
+    let addrs = addrs
+        .iter()
+        .map(|&addr| ResolveWhat::Address(addr))
+        .collect::<Vec<_>>();
+    Cache::with_global(|cache| cache.resolve_batch(&addrs, cb));
+}
+
+// unsafe because this is required to be externally synchronized
+#[cfg(feature = "std")]
+pub unsafe fn resolve_with_modules(
+    modules: &[crate::Module],
+    addrs: &[ResolveWhat<'_>],
+    cb: &mut dyn FnMut(usize, &super::Symbol),
+) {
+    let libraries = modules
+        .iter()
+        .map(|module| {
+            let mut lib = Library::from(module);
+            lib.file_id = file_id(module.path());
+            lib
+        })
+        .collect();
+    Cache::with_offline(|cache| {
+        cache.replace_libraries(libraries, Library::same_file);
+        cache.resolve_batch(addrs, cb);
+    });
+}
+
+// unsafe because this is required to be externally synchronized
+#[cfg(feature = "std")]
+pub unsafe fn find_modules(addrs: &[*mut c_void], cb: &mut dyn FnMut(usize, &crate::Module)) {
     Cache::with_global(|cache| {
-        let (lib, addr) = match cache.avma_to_svma(addr as *const u8) {
+        for (i, addr) in addrs.iter().enumerate() {
+            let addr = ResolveWhat::Address(*addr).address_or_ip();
+            if let Some((lib, _)) = cache.lookup(addr as *const u8) {
+                cb(i, &crate::Module::from(&cache.libraries[lib]));
+            }
+        }
+    });
+}
+
+// unsafe because this is required to be externally synchronized
+#[cfg(feature = "std")]
+pub unsafe fn modules() -> Vec<crate::Module> {
+    native_libraries().iter().map(crate::Module::from).collect()
+}
+
+/// Returns the modules loaded into the process `pid`, reading its memory
+/// through `read`.
+#[cfg(all(
+    feature = "ptrace",
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+))]
+pub fn remote_modules(
+    pid: u32,
+    read: &mut dyn FnMut(usize, &mut [u8]) -> bool,
+) -> Vec<crate::Module> {
+    libs_remote::remote_libraries(pid, read)
+        .iter()
+        .map(crate::Module::from)
+        .collect()
+}
+
+/// Returns the modules loaded at `mappings` in another process, reading its
+/// memory through `read`.
+#[cfg(all(
+    feature = "coredump",
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+))]
+pub fn mapped_modules(
+    mappings: &[(usize, usize, &mystd::ffi::OsStr)],
+    read: &mut dyn FnMut(usize, &mut [u8]) -> bool,
+) -> Vec<crate::Module> {
+    libs_remote::mapped_libraries(mappings, read)
+        .iter()
+        .map(crate::Module::from)
+        .collect()
+}
+
+impl Cache {
+    fn resolve(&mut self, what: ResolveWhat<'_>, cb: &mut dyn FnMut(&super::Symbol)) {
+        let addr = what.address_or_ip();
+        let adjustment = (what.ip() as usize).wrapping_sub(addr as usize);
+        let (lib, addr) = match self.lookup(addr as *const u8) {
             Some(pair) => pair,
             None => return,
         };
 
         // Finally, get a cached mapping or create a new mapping for this file, and
         // evaluate the DWARF info to find the file/line/name for this address.
-        let cx = match cache.mapping_for_lib(lib) {
+        let bias = self.libraries[lib].bias;
+        let cx = match self.mapping_for_lib(lib) {
             Some(cx) => cx,
             None => return,
         };
+        cx.resolve(addr, adjustment, bias, cb);
+    }
+
+    /// Resolves each of `addrs`, passing the index of the address along
+    /// with each symbol found for it to `cb`.
+    ///
+    /// The addresses are resolved one library at a time, so that the mapping
+    /// of each library is only looked up once, and in order of address within
+    /// each library to make the most of the caches of its `Context`.
+    #[cfg(feature = "std")]
+    fn resolve_batch(
+        &mut self,
+        addrs: &[ResolveWhat<'_>],
+        cb: &mut dyn FnMut(usize, &super::Symbol),
+    ) {
+        let lookups = self.lookup_batch(addrs);
+        let mut rest = &lookups[..];
+        while let Some(&(lib, _, _)) = rest.first() {
+            let len = rest
+                .iter()
+                .take_while(|&&(other, _, _)| other == lib)
+                .count();
+            let (group, tail) = rest.split_at(len);
+            rest = tail;
+
+            let bias = self.libraries[lib].bias;
+            let cx = match self.mapping_for_lib(lib) {
+                Some(cx) => cx,
+                None => continue,
+            };
+            for &(_, addr, i) in group {
+                let what = addrs[i];
+                let adjustment = (what.ip() as usize).wrapping_sub(what.address_or_ip() as usize);
+                cx.resolve(addr, adjustment, bias, &mut |sym| cb(i, sym));
+            }
+        }
+    }
+
+    /// Translates each of `addrs` like `lookup`, returning the library and
+    /// stated virtual memory address of each one found along with its index,
+    /// sorted by library and then by address.
+    ///
+    /// The list of libraries is brought up to date at most once for the whole
+    /// batch, so all of the library indices returned refer to the same list.
+    #[cfg(feature = "std")]
+    fn lookup_batch(&mut self, addrs: &[ResolveWhat<'_>]) -> Vec<(usize, *const u8, usize)> {
+        self.check_generation();
+        let translate = |cache: &Cache| {
+            addrs
+                .iter()
+                .map(|addr| cache.avma_to_svma(addr.address_or_ip() as *const u8))
+                .collect::<Vec<_>>()
+        };
+        let mut svmas = translate(self);
+        if self.native && self.generation.is_none() && svmas.iter().any(Option::is_none) {
+            self.refresh_libraries();
+            svmas = translate(self);
+        }
+
+        let mut lookups = svmas
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, svma)| {
+                let (lib, addr) = svma?;
+                Some((lib, addr, i))
+            })
+            .collect::<Vec<_>>();
+        lookups.sort_unstable_by_key(|&(lib, addr, _)| (lib, addr));
+        lookups
+    }
+}
+
+impl Context<'_> {
+    /// Resolves `addr`, a stated virtual memory address within this object,
+    /// yielding each symbol found for it (innermost inlined frame first).
+    /// Addresses given out in symbols have `bias` added to them.
+    ///
+    /// The instruction pointer which offsets into functions are measured from
+    /// is `adjustment` bytes after `addr`, see `ResolveWhat::address_or_ip`.
+    fn resolve(
+        &mut self,
+        addr: *const u8,
+        adjustment: usize,
+        bias: usize,
+        cb: &mut dyn FnMut(&super::Symbol),
+    ) {
+        let mut call = |sym: Symbol<'_>| {
+            // Extend the lifetime of `sym` to `'static` since we are unfortunately
+            // required to here, but it's only ever going out as a reference so no
+            // reference to it should be persisted beyond this frame anyway.
+            let sym = unsafe { mem::transmute::<Symbol<'_>, Symbol<'static>>(sym) };
+            (cb)(&super::Symbol { inner: sym });
+        };
+
+        let cx = self;
+        let addr = addr as u64;
+        let function_at = |address: u64, size: Option<u64>| Function {
+            address: (address as usize).wrapping_add(bias),
+            size: size.map(|size| size as usize),
+            offset: (addr as usize)
+                .wrapping_add(adjustment)
+                .wrapping_sub(address as usize),
+        };
+        let mut function = cx
+            .function(addr)
+            .map(|(address, size)| function_at(address, size));
+        let queried = (addr as usize).wrapping_add(bias) as *mut c_void;
         let mut any_frames = false;
-        if let Ok(mut frames) = cx.dwarf.find_frames(addr as u64) {
-            while let Ok(Some(frame)) = frames.next() {
-                any_frames = true;
-                let name = match frame.function {
-                    Some(f) => Some(f.name.slice()),
-                    None => cx.object.search_symtab(addr as u64),
-                };
-                call(Symbol::Frame {
-                    addr: addr as *mut c_void,
-                    location: frame.location,
-                    name,
-                });
+        {
+            // The skeleton unit of split DWARF only knows the location of
+            // `addr`, so the functions there are looked up in its split unit.
+            let object = &cx.object;
+            let location = cx
+                .dwarf
+                .find_location(addr)
+                .ok()
+                .and_then(|location| location);
+            if let Some(unit) = cx.split.find_unit(&cx.dwarf, addr) {
+                if function.is_none() {
+                    function = unit
+                        .function(addr)
+                        .map(|(address, size)| function_at(address, Some(size)));
+                }
+                any_frames = resolve_frames(
+                    queried,
+                    function,
+                    &mut unit.frames(addr, location).into_iter(),
+                    &mut || Some(object.search_symtab(addr)?.name),
+                    &mut call,
+                );
             }
         }
         if !any_frames {
-            if let Some((object_cx, object_addr)) = cx.object.search_object_map(addr as u64) {
+            if let Ok(mut frames) = cx.dwarf.find_frames(addr) {
+                any_frames = resolve_frames(
+                    queried,
+                    function,
+                    &mut core::iter::from_fn(|| next_frame(&mut frames)),
+                    &mut || Some(cx.object.search_symtab(addr)?.name),
+                    &mut call,
+                );
+            }
+        }
+        if !any_frames {
+            if let Some((object_cx, object_addr)) = cx.object.search_object_map(addr) {
                 if let Ok(mut frames) = object_cx.dwarf.find_frames(object_addr) {
-                    while let Ok(Some(frame)) = frames.next() {
-                        any_frames = true;
-                        call(Symbol::Frame {
-                            addr: addr as *mut c_void,
-                            location: frame.location,
-                            name: frame.function.map(|f| f.name.slice()),
-                        });
-                    }
+                    any_frames = resolve_frames(
+                        queried,
+                        function,
+                        &mut core::iter::from_fn(|| next_frame(&mut frames)),
+                        &mut || None,
+                        &mut call,
+                    );
                 }
             }
         }
         if !any_frames {
-            if let Some(name) = cx.object.search_symtab(addr as u64) {
+            if let Some(sym) = cx.object.search_symtab(addr) {
                 call(Symbol::Symtab {
-                    addr: addr as *mut c_void,
-                    name,
+                    addr: queried,
+                    name: sym.name,
+                    function,
                 });
             }
         }
-    });
+    }
+
+    /// Returns the stated virtual memory address and the size of the function
+    /// which `addr` is in, from the symbol table or else from the DWARF.
+    fn function(&mut self, addr: u64) -> Option<(u64, Option<u64>)> {
+        if let Some(sym) = self.object.search_symtab(addr) {
+            return Some((sym.address, sym.size));
+        }
+        // The subprograms of the unit are only gathered the first time one of
+        // them is looked for.
+        let unit = self.dwarf.find_dwarf_unit(addr)?;
+        let offset = unit.header.offset();
+        let i = match self
+            .subprograms
+            .binary_search_by_key(&offset, |&(offset, _)| offset)
+        {
+            Ok(i) => i,
+            Err(i) => {
+                let subprograms = subprograms(self.dwarf.dwarf(), unit);
+                self.subprograms.insert(i, (offset, subprograms));
+                i
+            }
+        };
+        let &(begin, end, _) = find_subprogram(&self.subprograms[i].1, addr)?;
+        Some((begin, Some(end - begin)))
+    }
+}
+
+/// The name of a function, if known, and the location in it.
+type Frame<'a> = (Option<&'a [u8]>, Option<addr2line::Location<'a>>);
+
+/// Returns the next frame of `frames`, or `None` once there are no more or
+/// there's an error.
+fn next_frame<'ctx>(
+    frames: &mut addr2line::FrameIter<'ctx, EndianSlice<'_, Endian>>,
+) -> Option<Frame<'ctx>> {
+    let frame = frames.next().ok()??;
+    Some((frame.function.map(|f| f.name.slice()), frame.location))
+}
+
+/// Passes a symbol for each of `frames` to `call`, returning whether there were
+/// any. Frames without a name are given the one `symtab_name` returns.
+///
+/// Every frame but the last was inlined into the frame after it, at the
+/// location that frame is at, so each frame is held back until the next one is
+/// known.
+fn resolve_frames<'ctx>(
+    addr: *mut c_void,
+    function: Option<Function>,
+    frames: &mut dyn Iterator<Item = Frame<'ctx>>,
+    symtab_name: &mut dyn FnMut() -> Option<&'ctx [u8]>,
+    call: &mut dyn FnMut(Symbol<'ctx>),
+) -> bool {
+    let mut pending = None;
+    for (name, frame_location) in frames {
+        let name = name.or_else(&mut *symtab_name);
+        if let Some((location, name)) = pending.take() {
+            call(Symbol::Frame {
+                addr,
+                location,
+                name,
+                function,
+                inlined: true,
+                call_location: frame_location.as_ref().map(copy_location),
+            });
+        }
+        pending = Some((frame_location, name));
+    }
+    match pending {
+        Some((location, name)) => {
+            call(Symbol::Frame {
+                addr,
+                location,
+                name,
+                function,
+                inlined: false,
+                call_location: None,
+            });
+            true
+        }
+        None => false,
+    }
+}
+
+fn copy_location<'a>(location: &addr2line::Location<'a>) -> addr2line::Location<'a> {
+    addr2line::Location {
+        file: location.file,
+        line: location.line,
+        column: location.column,
+    }
+}
+
+/// A symbolizer for a single object file on disk, see `crate::Symbolizer`.
+#[cfg(feature = "std")]
+pub struct Symbolizer {
+    mapping: Mapping,
+}
+
+#[cfg(feature = "std")]
+impl Symbolizer {
+    // unsafe because this is required to be externally synchronized
+    pub unsafe fn new(path: &Path) -> Option<Symbolizer> {
+        let mapping = Mapping::new(path);
+        report_diagnostics();
+        Some(Symbolizer { mapping: mapping? })
+    }
+
+    pub fn build_id(&self) -> Option<&[u8]> {
+        self.mapping.cx.object.build_id()
+    }
+
+    pub fn resolve(&mut self, addr: u64, cb: &mut dyn FnMut(&super::Symbol)) {
+        let addr: usize = match addr.try_into() {
+            Ok(addr) => addr,
+            Err(_) => return,
+        };
+        self.mapping.cx.resolve(addr as *const u8, 0, 0, cb);
+    }
+}
+
+/// A symbol found in the symbol table of an object, see `search_symtab`.
+pub struct SymtabSymbol<'a> {
+    name: &'a [u8],
+    /// The stated virtual memory address of the symbol.
+    address: u64,
+    /// The size of the symbol, if the object format records it.
+    size: Option<u64>,
+}
+
+/// The function an address being resolved is in. For inlined functions this is
+/// the function they were inlined into.
+#[derive(Copy, Clone)]
+pub struct Function {
+    address: usize,
+    size: Option<usize>,
+    /// The offset of the instruction pointer being resolved from `address`.
+    offset: usize,
 }
 
 pub enum Symbol<'a> {
@@ -404,10 +1247,18 @@ pub enum Symbol<'a> {
         addr: *mut c_void,
         location: Option<addr2line::Location<'a>>,
         name: Option<&'a [u8]>,
+        function: Option<Function>,
+        /// Whether the function was inlined into its caller, and if so where.
+        inlined: bool,
+        call_location: Option<addr2line::Location<'a>>,
     },
     /// Couldn't find debug information, but we found it in the symbol table of
     /// the elf executable.
-    Symtab { addr: *mut c_void, name: &'a [u8] },
+    Symtab {
+        addr: *mut c_void,
+        name: &'a [u8],
+        function: Option<Function>,
+    },
 }
 
 impl Symbol<'_> {
@@ -423,11 +1274,32 @@ impl Symbol<'_> {
 
     pub fn addr(&self) -> Option<*mut c_void> {
         match self {
+            Symbol::Frame { function, .. } | Symbol::Symtab { function, .. }
+                if function.is_some() =>
+            {
+                function.map(|f| f.address as *mut c_void)
+            }
+            // Without knowing where the function starts the best we can do
+            // is the address being resolved.
             Symbol::Frame { addr, .. } => Some(*addr),
             Symbol::Symtab { .. } => None,
         }
     }
 
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            Symbol::Frame { function, .. } | Symbol::Symtab { function, .. } => (*function)?.size,
+        }
+    }
+
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Symbol::Frame { function, .. } | Symbol::Symtab { function, .. } => {
+                Some((*function)?.offset)
+            }
+        }
+    }
+
     pub fn filename_raw(&self) -> Option<BytesOrWideString<'_>> {
         match self {
             Symbol::Frame { location, .. } => {
@@ -461,4 +1333,45 @@ impl Symbol<'_> {
             Symbol::Symtab { .. } => None,
         }
     }
+
+    pub fn is_inlined(&self) -> bool {
+        match self {
+            Symbol::Frame { inlined, .. } => *inlined,
+            Symbol::Symtab { .. } => false,
+        }
+    }
+
+    pub fn call_filename_raw(&self) -> Option<BytesOrWideString<'_>> {
+        match self {
+            Symbol::Frame { call_location, .. } => {
+                let file = call_location.as_ref()?.file?;
+                Some(BytesOrWideString::Bytes(file.as_bytes()))
+            }
+            Symbol::Symtab { .. } => None,
+        }
+    }
+
+    pub fn call_filename(&self) -> Option<&Path> {
+        match self {
+            Symbol::Frame { call_location, .. } => {
+                let file = call_location.as_ref()?.file?;
+                Some(Path::new(file))
+            }
+            Symbol::Symtab { .. } => None,
+        }
+    }
+
+    pub fn call_lineno(&self) -> Option<u32> {
+        match self {
+            Symbol::Frame { call_location, .. } => call_location.as_ref()?.line,
+            Symbol::Symtab { .. } => None,
+        }
+    }
+
+    pub fn call_colno(&self) -> Option<u32> {
+        match self {
+            Symbol::Frame { call_location, .. } => call_location.as_ref()?.column,
+            Symbol::Symtab { .. } => None,
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use super::{Context, Mapping, Path, Stash, Vec};
+use super::{Context, Mapping, Path, Stash, SymtabSymbol, Vec};
 use core::convert::TryFrom;
 use object::pe::{ImageDosHeader, ImageSymbol};
 use object::read::pe::{ImageNtHeaders, ImageOptionalHeader, SectionTable};
@@ -26,13 +26,6 @@ pub struct Object<'a> {
     strings: StringTable<'a>,
 }
 
-pub fn get_image_base(data: &[u8]) -> Option<usize> {
-    let dos_header = ImageDosHeader::parse(data).ok()?;
-    let mut offset = dos_header.nt_headers_offset().into();
-    let (nt_headers, _) = Pe::parse(data, &mut offset).ok()?;
-    usize::try_from(nt_headers.optional_header().image_base()).ok()
-}
-
 impl<'a> Object<'a> {
     fn parse(data: &'a [u8]) -> Option<Object<'a>> {
         let dos_header = ImageDosHeader::parse(data).ok()?;
@@ -84,7 +77,7 @@ impl<'a> Object<'a> {
         )
     }
 
-    pub fn search_symtab<'b>(&'b self, addr: u64) -> Option<&'b [u8]> {
+    pub fn search_symtab<'b>(&'b self, addr: u64) -> Option<SymtabSymbol<'b>> {
         // Note that unlike other formats COFF doesn't embed the size of
         // each symbol. As a last ditch effort search for the *closest*
         // symbol to a particular address and return that one. This gets
@@ -99,10 +92,19 @@ impl<'a> Object<'a> {
             // greatest less than `addr`
             Err(i) => i.checked_sub(1)?,
         };
-        self.symbols[i].1.name(self.strings).ok()
+        let (address, sym) = &self.symbols[i];
+        Some(SymtabSymbol {
+            name: sym.name(self.strings).ok()?,
+            address: *address as u64,
+            size: None,
+        })
     }
 
     pub(super) fn search_object_map(&self, _addr: u64) -> Option<(&Context<'_>, u64)> {
         None
     }
+
+    pub(super) fn build_id(&self) -> Option<&'a [u8]> {
+        None
+    }
 }
@@ -1,13 +1,16 @@
-use super::mystd::ffi::{OsStr, OsString};
+use super::mystd::env;
+use super::mystd::ffi::OsStr;
 use super::mystd::fs;
-use super::mystd::os::unix::ffi::{OsStrExt, OsStringExt};
+use super::mystd::os::unix::ffi::OsStrExt;
 use super::mystd::path::{Path, PathBuf};
 use super::Either;
-use super::{Context, Mapping, Stash, Vec};
+use super::{Context, Mapping, SplitDwarf, Stash, SymtabSymbol, Vec};
 use core::convert::{TryFrom, TryInto};
 use core::str;
 use object::elf::{ELFCOMPRESS_ZLIB, ELF_NOTE_GNU, NT_GNU_BUILD_ID, SHF_COMPRESSED};
-use object::read::elf::{CompressionHeader, FileHeader, SectionHeader, SectionTable, Sym};
+use object::read::elf::{
+    CompressionHeader, FileHeader, SectionHeader, SectionTable, Sym, SymbolTable,
+};
 use object::read::StringTable;
 use object::{BigEndian, Bytes, NativeEndian};
 
@@ -20,7 +23,7 @@ impl Mapping {
     pub fn new(path: &Path) -> Option<Mapping> {
         let map = super::mmap(path)?;
         Mapping::mk_or_other(map, |map, stash| {
-            let object = Object::parse(&map)?;
+            let object = Object::parse(&map, stash)?;
 
             // Try to locate an external debug file using the build ID.
             if let Some(path_debug) = object.build_id().and_then(locate_build_id) {
@@ -30,13 +33,17 @@ impl Mapping {
             }
 
             // Try to locate an external debug file using the GNU debug link section.
-            if let Some((path_debug, crc)) = object.gnu_debuglink_path(path) {
-                if let Some(mapping) = Mapping::new_debug(path_debug, Some(crc)) {
-                    return Some(Either::A(mapping));
+            if let Some((paths_debug, crc)) = object.gnu_debuglink_paths(path) {
+                for path_debug in paths_debug {
+                    if let Some(mapping) = Mapping::new_debug(path_debug, Some(crc)) {
+                        return Some(Either::A(mapping));
+                    }
                 }
             }
 
-            Context::new(stash, object, None).map(Either::B)
+            let mut cx = Context::new(stash, object, None)?;
+            cx.split = SplitDwarf::new(Some(path));
+            Some(Either::B(cx))
         })
     }
 
@@ -44,25 +51,40 @@ impl Mapping {
     fn new_debug(path: PathBuf, crc: Option<u32>) -> Option<Mapping> {
         let map = super::mmap(&path)?;
         Mapping::mk(map, |map, stash| {
-            let object = Object::parse(&map)?;
-
-            if let Some(_crc) = crc {
-                // TODO: check crc
+            let object = Object::parse(&map, stash)?;
+
+            // A debug file found through its name alone may well be stale, so
+            // make sure that it really belongs to the file that named it.
+            if let Some(expected) = crc {
+                let actual = crc32(map);
+                if actual != expected {
+                    #[cfg(feature = "std")]
+                    super::diagnose(crate::Diagnostic::DebuglinkCrcMismatch {
+                        path: &path,
+                        expected,
+                        actual,
+                    });
+                    return None;
+                }
             }
 
             // Try to locate a supplementary object file.
             if let Some((path_sup, build_id_sup)) = object.gnu_debugaltlink_path(&path) {
                 if let Some(map_sup) = super::mmap(&path_sup) {
-                    let map_sup = stash.set_mmap_aux(map_sup);
-                    if let Some(sup) = Object::parse(map_sup) {
+                    let map_sup = stash.cache_mmap(map_sup);
+                    if let Some(sup) = Object::parse(map_sup, stash) {
                         if sup.build_id() == Some(build_id_sup) {
-                            return Context::new(stash, object, Some(sup));
+                            let mut cx = Context::new(stash, object, Some(sup))?;
+                            cx.split = SplitDwarf::new(Some(&path));
+                            return Some(cx);
                         }
                     }
                 }
             }
 
-            Context::new(stash, object, None)
+            let mut cx = Context::new(stash, object, None)?;
+            cx.split = SplitDwarf::new(Some(&path));
+            Some(cx)
         })
     }
 }
@@ -71,6 +93,9 @@ struct ParsedSym {
     address: u64,
     size: u64,
     name: u32,
+    /// Whether the symbol comes from the MiniDebugInfo in `.gnu_debugdata`,
+    /// in which case `name` is an offset into its string table instead.
+    debugdata: bool,
 }
 
 pub struct Object<'a> {
@@ -82,57 +107,48 @@ pub struct Object<'a> {
     data: &'a [u8],
     sections: SectionTable<'a, Elf>,
     strings: StringTable<'a>,
+    /// The string table of the symbols read from `.gnu_debugdata`, if any.
+    debugdata_strings: StringTable<'a>,
     /// List of pre-parsed and sorted symbols by base address.
     syms: Vec<ParsedSym>,
 }
 
 impl<'a> Object<'a> {
-    fn parse(data: &'a [u8]) -> Option<Object<'a>> {
+    #[cfg_attr(not(feature = "lzma-rs"), allow(unused_variables))]
+    fn parse(data: &'a [u8], stash: &'a Stash) -> Option<Object<'a>> {
         let elf = Elf::parse(data).ok()?;
         let endian = elf.endian().ok()?;
         let sections = elf.sections(endian, data).ok()?;
-        let mut syms = sections
+        let mut symtab = sections
             .symbols(endian, data, object::elf::SHT_SYMTAB)
             .ok()?;
-        if syms.is_empty() {
-            syms = sections
+        let mut syms = Vec::new();
+        #[allow(unused_mut)]
+        let mut debugdata_strings = StringTable::default();
+        if symtab.is_empty() {
+            symtab = sections
                 .symbols(endian, data, object::elf::SHT_DYNSYM)
                 .ok()?;
-        }
-        let strings = syms.strings();
 
-        let mut syms = syms
-            .iter()
-            // Only look at function/object symbols. This mirrors what
-            // libbacktrace does and in general we're only symbolicating
-            // function addresses in theory. Object symbols correspond
-            // to data, and maybe someone's crazy enough to have a
-            // function go into static data?
-            .filter(|sym| {
-                let st_type = sym.st_type();
-                st_type == object::elf::STT_FUNC || st_type == object::elf::STT_OBJECT
-            })
-            // skip anything that's in an undefined section header,
-            // since it means it's an imported function and we're only
-            // symbolicating with locally defined functions.
-            .filter(|sym| sym.st_shndx(endian) != object::elf::SHN_UNDEF)
-            .map(|sym| {
-                let address = sym.st_value(endian).into();
-                let size = sym.st_size(endian).into();
-                let name = sym.st_name(endian);
-                ParsedSym {
-                    address,
-                    size,
-                    name,
+            // Binaries stripped this way may still carry a symbol table for
+            // their local functions, which aren't in `.dynsym`, as an xz
+            // compressed ELF file in `.gnu_debugdata`.
+            #[cfg(feature = "lzma-rs")]
+            {
+                if let Some(debugdata) = debugdata_symbols(endian, &sections, data, stash) {
+                    push_syms(&mut syms, endian, &debugdata, true);
+                    debugdata_strings = debugdata.strings();
                 }
-            })
-            .collect::<Vec<_>>();
+            }
+        }
+        push_syms(&mut syms, endian, &symtab, false);
         syms.sort_unstable_by_key(|s| s.address);
         Some(Object {
             endian,
             data,
             sections,
-            strings,
+            strings: symtab.strings(),
+            debugdata_strings,
             syms,
         })
     }
@@ -142,7 +158,8 @@ impl<'a> Object<'a> {
             let mut data = Bytes(section.data(self.endian, self.data).ok()?);
 
             // Check for DWARF-standard (gABI) compression, i.e., as generated
-            // by ld's `--compress-debug-sections=zlib-gabi` flag.
+            // by ld's `--compress-debug-sections=zlib-gabi` and
+            // `--compress-debug-sections=zstd` flags.
             let flags: u64 = section.sh_flags(self.endian).into();
             if (flags & u64::from(SHF_COMPRESSED)) == 0 {
                 // Not compressed.
@@ -150,14 +167,24 @@ impl<'a> Object<'a> {
             }
 
             let header = data.read::<<Elf as FileHeader>::CompressionHeader>().ok()?;
-            if header.ch_type(self.endian) != ELFCOMPRESS_ZLIB {
-                // Zlib compression is the only known type.
-                return None;
+            match header.ch_type(self.endian) {
+                ELFCOMPRESS_ZLIB => {
+                    let size = usize::try_from(header.ch_size(self.endian)).ok()?;
+                    let buf = stash.allocate(size);
+                    decompress_zlib(data.0, buf)?;
+                    return Some(buf);
+                }
+                #[cfg(feature = "ruzstd")]
+                ELFCOMPRESS_ZSTD => {
+                    let size = usize::try_from(header.ch_size(self.endian)).ok()?;
+                    let buf = stash.allocate(size);
+                    decompress_zstd(data.0, buf)?;
+                    return Some(buf);
+                }
+                // Unknown compression type, or zstd without the `ruzstd`
+                // feature enabled.
+                _ => return None,
             }
-            let size = usize::try_from(header.ch_size(self.endian)).ok()?;
-            let buf = stash.allocate(size);
-            decompress_zlib(data.0, buf)?;
-            return Some(buf);
         }
 
         // Check for the nonstandard GNU compression format, i.e., as generated
@@ -196,7 +223,7 @@ impl<'a> Object<'a> {
             .map(|(_index, section)| section)
     }
 
-    pub fn search_symtab<'b>(&'b self, addr: u64) -> Option<&'b [u8]> {
+    pub fn search_symtab<'b>(&'b self, addr: u64) -> Option<SymtabSymbol<'b>> {
         // Same sort of binary search as Windows above
         let i = match self.syms.binary_search_by_key(&addr, |sym| sym.address) {
             Ok(i) => i,
@@ -204,7 +231,16 @@ impl<'a> Object<'a> {
         };
         let sym = self.syms.get(i)?;
         if sym.address <= addr && addr <= sym.address + sym.size {
-            self.strings.get(sym.name).ok()
+            let strings = if sym.debugdata {
+                &self.debugdata_strings
+            } else {
+                &self.strings
+            };
+            Some(SymtabSymbol {
+                name: strings.get(sym.name).ok()?,
+                address: sym.address,
+                size: Some(sym.size).filter(|&size| size != 0),
+            })
         } else {
             None
         }
@@ -214,7 +250,7 @@ impl<'a> Object<'a> {
         None
     }
 
-    fn build_id(&self) -> Option<&'a [u8]> {
+    pub(super) fn build_id(&self) -> Option<&'a [u8]> {
         for section in self.sections.iter() {
             if let Ok(Some(mut notes)) = section.notes(self.endian, self.data) {
                 while let Ok(Some(note)) = notes.next() {
@@ -229,7 +265,7 @@ impl<'a> Object<'a> {
 
     // The contents of the ".gnu_debuglink" section is documented at:
     // https://sourceware.org/gdb/onlinedocs/gdb/Separate-Debug-Files.html
-    fn gnu_debuglink_path(&self, path: &Path) -> Option<(PathBuf, u32)> {
+    fn gnu_debuglink_paths(&self, path: &Path) -> Option<(Vec<PathBuf>, u32)> {
         let section = self.section_header(".gnu_debuglink")?;
         let data = section.data(self.endian, self.data).ok()?;
         let len = data.iter().position(|x| *x == 0)?;
@@ -239,8 +275,7 @@ impl<'a> Object<'a> {
             .get(offset..offset + 4)
             .and_then(|bytes| bytes.try_into().ok())?;
         let crc = u32::from_ne_bytes(crc_bytes);
-        let path_debug = locate_debuglink(path, filename)?;
-        Some((path_debug, crc))
+        Some((locate_debuglink(path, filename), crc))
     }
 
     // The format of the ".gnu_debugaltlink" section is based on gdb.
@@ -255,6 +290,111 @@ impl<'a> Object<'a> {
     }
 }
 
+/// Appends the symbols of `symtab` which may be looked up by address to `syms`.
+fn push_syms(
+    syms: &mut Vec<ParsedSym>,
+    endian: NativeEndian,
+    symtab: &SymbolTable<'_, Elf>,
+    debugdata: bool,
+) {
+    let parsed = symtab
+        .iter()
+        // Only look at function/object symbols. This mirrors what
+        // libbacktrace does and in general we're only symbolicating
+        // function addresses in theory. Object symbols correspond
+        // to data, and maybe someone's crazy enough to have a
+        // function go into static data?
+        .filter(|sym| {
+            let st_type = sym.st_type();
+            st_type == object::elf::STT_FUNC || st_type == object::elf::STT_OBJECT
+        })
+        // skip anything that's in an undefined section header,
+        // since it means it's an imported function and we're only
+        // symbolicating with locally defined functions.
+        .filter(|sym| sym.st_shndx(endian) != object::elf::SHN_UNDEF)
+        .map(|sym| ParsedSym {
+            address: sym.st_value(endian).into(),
+            size: sym.st_size(endian).into(),
+            name: sym.st_name(endian),
+            debugdata,
+        });
+    syms.extend(parsed);
+}
+
+/// Decompresses the "MiniDebugInfo" in the `.gnu_debugdata` section, an ELF
+/// file compressed with xz, and returns its symbol table.
+///
+/// The decompressed file is kept in `stash` so that the names of its symbols
+/// live as long as those of the file it's embedded in.
+#[cfg(feature = "lzma-rs")]
+fn debugdata_symbols<'a>(
+    endian: NativeEndian,
+    sections: &SectionTable<'a, Elf>,
+    data: &'a [u8],
+    stash: &'a Stash,
+) -> Option<SymbolTable<'a, Elf>> {
+    let (_index, section) = sections.section_by_name(endian, b".gnu_debugdata")?;
+    let mut input = section.data(endian, data).ok()?;
+    let buf = stash.allocate(xz_decompressed_size(input)?);
+    let mut output = &mut buf[..];
+    lzma_rs::xz_decompress(&mut input, &mut output).ok()?;
+    if !output.is_empty() {
+        return None;
+    }
+    let buf: &'a [u8] = buf;
+
+    let elf = Elf::parse(buf).ok()?;
+    let endian = elf.endian().ok()?;
+    elf.sections(endian, buf)
+        .ok()?
+        .symbols(endian, buf, object::elf::SHT_SYMTAB)
+        .ok()
+}
+
+/// Returns the size of the xz stream `input` once decompressed, which is the
+/// sum of the sizes of its blocks as listed in the index at the end of it.
+#[cfg(feature = "lzma-rs")]
+fn xz_decompressed_size(input: &[u8]) -> Option<usize> {
+    // The stream ends in a 12 byte footer: a CRC-32, the size of the index in
+    // units of four bytes less one, the stream flags and the magic "YZ".
+    let footer = input.get(input.len().checked_sub(12)?..)?;
+    if &footer[10..] != b"YZ" {
+        return None;
+    }
+    let index_size = (u32::from_le_bytes(footer[4..8].try_into().ok()?) as usize + 1) * 4;
+    let index = input.get(input.len().checked_sub(12 + index_size)?..)?;
+
+    // The index starts with a zero byte and the number of records, each of
+    // which holds the compressed and the decompressed size of a block.
+    let (&indicator, mut index) = index.split_first()?;
+    if indicator != 0 {
+        return None;
+    }
+    let records = xz_multibyte(&mut index)?;
+    let mut size = 0usize;
+    for _ in 0..records {
+        xz_multibyte(&mut index)?;
+        size = size.checked_add(usize::try_from(xz_multibyte(&mut index)?).ok()?)?;
+    }
+    Some(size)
+}
+
+/// Reads an integer in the variable length encoding of xz from the start of
+/// `input`.
+#[cfg(feature = "lzma-rs")]
+fn xz_multibyte(input: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for i in 0..9 {
+        let (&byte, rest) = input.split_first()?;
+        *input = rest;
+        value |= u64::from(byte & 0x7f) << (i * 7);
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
 fn decompress_zlib(input: &[u8], output: &mut [u8]) -> Option<()> {
     use miniz_oxide::inflate::core::inflate_flags::{
         TINFL_FLAG_PARSE_ZLIB_HEADER, TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF,
@@ -276,8 +416,105 @@ fn decompress_zlib(input: &[u8], output: &mut [u8]) -> Option<()> {
     }
 }
 
+/// Computes the CRC-32 checksum used by `.gnu_debuglink` sections, which is
+/// the same as the one used by zlib.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc = CRC32_TABLE[((crc ^ u32::from(*byte)) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// The CRC-32 of each byte value, for the reversed polynomial `0xedb88320`.
+/// See the `crc32_table` test for how it's computed.
+const CRC32_TABLE: [u32; 256] = [
+    0x00000000, 0x77073096, 0xee0e612c, 0x990951ba, 0x076dc419, 0x706af48f, 0xe963a535, 0x9e6495a3,
+    0x0edb8832, 0x79dcb8a4, 0xe0d5e91e, 0x97d2d988, 0x09b64c2b, 0x7eb17cbd, 0xe7b82d07, 0x90bf1d91,
+    0x1db71064, 0x6ab020f2, 0xf3b97148, 0x84be41de, 0x1adad47d, 0x6ddde4eb, 0xf4d4b551, 0x83d385c7,
+    0x136c9856, 0x646ba8c0, 0xfd62f97a, 0x8a65c9ec, 0x14015c4f, 0x63066cd9, 0xfa0f3d63, 0x8d080df5,
+    0x3b6e20c8, 0x4c69105e, 0xd56041e4, 0xa2677172, 0x3c03e4d1, 0x4b04d447, 0xd20d85fd, 0xa50ab56b,
+    0x35b5a8fa, 0x42b2986c, 0xdbbbc9d6, 0xacbcf940, 0x32d86ce3, 0x45df5c75, 0xdcd60dcf, 0xabd13d59,
+    0x26d930ac, 0x51de003a, 0xc8d75180, 0xbfd06116, 0x21b4f4b5, 0x56b3c423, 0xcfba9599, 0xb8bda50f,
+    0x2802b89e, 0x5f058808, 0xc60cd9b2, 0xb10be924, 0x2f6f7c87, 0x58684c11, 0xc1611dab, 0xb6662d3d,
+    0x76dc4190, 0x01db7106, 0x98d220bc, 0xefd5102a, 0x71b18589, 0x06b6b51f, 0x9fbfe4a5, 0xe8b8d433,
+    0x7807c9a2, 0x0f00f934, 0x9609a88e, 0xe10e9818, 0x7f6a0dbb, 0x086d3d2d, 0x91646c97, 0xe6635c01,
+    0x6b6b51f4, 0x1c6c6162, 0x856530d8, 0xf262004e, 0x6c0695ed, 0x1b01a57b, 0x8208f4c1, 0xf50fc457,
+    0x65b0d9c6, 0x12b7e950, 0x8bbeb8ea, 0xfcb9887c, 0x62dd1ddf, 0x15da2d49, 0x8cd37cf3, 0xfbd44c65,
+    0x4db26158, 0x3ab551ce, 0xa3bc0074, 0xd4bb30e2, 0x4adfa541, 0x3dd895d7, 0xa4d1c46d, 0xd3d6f4fb,
+    0x4369e96a, 0x346ed9fc, 0xad678846, 0xda60b8d0, 0x44042d73, 0x33031de5, 0xaa0a4c5f, 0xdd0d7cc9,
+    0x5005713c, 0x270241aa, 0xbe0b1010, 0xc90c2086, 0x5768b525, 0x206f85b3, 0xb966d409, 0xce61e49f,
+    0x5edef90e, 0x29d9c998, 0xb0d09822, 0xc7d7a8b4, 0x59b33d17, 0x2eb40d81, 0xb7bd5c3b, 0xc0ba6cad,
+    0xedb88320, 0x9abfb3b6, 0x03b6e20c, 0x74b1d29a, 0xead54739, 0x9dd277af, 0x04db2615, 0x73dc1683,
+    0xe3630b12, 0x94643b84, 0x0d6d6a3e, 0x7a6a5aa8, 0xe40ecf0b, 0x9309ff9d, 0x0a00ae27, 0x7d079eb1,
+    0xf00f9344, 0x8708a3d2, 0x1e01f268, 0x6906c2fe, 0xf762575d, 0x806567cb, 0x196c3671, 0x6e6b06e7,
+    0xfed41b76, 0x89d32be0, 0x10da7a5a, 0x67dd4acc, 0xf9b9df6f, 0x8ebeeff9, 0x17b7be43, 0x60b08ed5,
+    0xd6d6a3e8, 0xa1d1937e, 0x38d8c2c4, 0x4fdff252, 0xd1bb67f1, 0xa6bc5767, 0x3fb506dd, 0x48b2364b,
+    0xd80d2bda, 0xaf0a1b4c, 0x36034af6, 0x41047a60, 0xdf60efc3, 0xa867df55, 0x316e8eef, 0x4669be79,
+    0xcb61b38c, 0xbc66831a, 0x256fd2a0, 0x5268e236, 0xcc0c7795, 0xbb0b4703, 0x220216b9, 0x5505262f,
+    0xc5ba3bbe, 0xb2bd0b28, 0x2bb45a92, 0x5cb36a04, 0xc2d7ffa7, 0xb5d0cf31, 0x2cd99e8b, 0x5bdeae1d,
+    0x9b64c2b0, 0xec63f226, 0x756aa39c, 0x026d930a, 0x9c0906a9, 0xeb0e363f, 0x72076785, 0x05005713,
+    0x95bf4a82, 0xe2b87a14, 0x7bb12bae, 0x0cb61b38, 0x92d28e9b, 0xe5d5be0d, 0x7cdcefb7, 0x0bdbdf21,
+    0x86d3d2d4, 0xf1d4e242, 0x68ddb3f8, 0x1fda836e, 0x81be16cd, 0xf6b9265b, 0x6fb077e1, 0x18b74777,
+    0x88085ae6, 0xff0f6a70, 0x66063bca, 0x11010b5c, 0x8f659eff, 0xf862ae69, 0x616bffd3, 0x166ccf45,
+    0xa00ae278, 0xd70dd2ee, 0x4e048354, 0x3903b3c2, 0xa7672661, 0xd06016f7, 0x4969474d, 0x3e6e77db,
+    0xaed16a4a, 0xd9d65adc, 0x40df0b66, 0x37d83bf0, 0xa9bcae53, 0xdebb9ec5, 0x47b2cf7f, 0x30b5ffe9,
+    0xbdbdf21c, 0xcabac28a, 0x53b39330, 0x24b4a3a6, 0xbad03605, 0xcdd70693, 0x54de5729, 0x23d967bf,
+    0xb3667a2e, 0xc4614ab8, 0x5d681b02, 0x2a6f2b94, 0xb40bbe37, 0xc30c8ea1, 0x5a05df1b, 0x2d02ef8d,
+];
+
+/// The `ch_type` of sections compressed with zstd, which the `object` crate
+/// doesn't know about yet.
+#[cfg(feature = "ruzstd")]
+const ELFCOMPRESS_ZSTD: u32 = 2;
+
+#[cfg(feature = "ruzstd")]
+fn decompress_zstd(mut input: &[u8], mut output: &mut [u8]) -> Option<()> {
+    use ruzstd::frame::ReadFrameHeaderError;
+    use ruzstd::frame_decoder::FrameDecoderError;
+    use ruzstd::io::Read;
+
+    // The section may consist of several frames, each of which has to be
+    // decoded in turn. Skippable frames carry no data and are passed over.
+    while !input.is_empty() {
+        let mut decoder = match ruzstd::StreamingDecoder::new(&mut input) {
+            Ok(decoder) => decoder,
+            Err(FrameDecoderError::ReadFrameHeaderError(ReadFrameHeaderError::SkipFrame {
+                length,
+                ..
+            })) => {
+                input = input.get(usize::try_from(length).ok()?..)?;
+                continue;
+            }
+            Err(_) => return None,
+        };
+        while !output.is_empty() {
+            let bytes_written = decoder.read(output).ok()?;
+            if bytes_written == 0 {
+                break;
+            }
+            output = &mut output[bytes_written..];
+        }
+        // Reading on once `output` is full finishes the frame, so that the
+        // next one starts where it ends, and checks that it had no more data.
+        if decoder.read(&mut [0]).ok()? != 0 {
+            return None;
+        }
+    }
+
+    if output.is_empty() {
+        Some(())
+    } else {
+        None
+    }
+}
+
 const DEBUG_PATH: &[u8] = b"/usr/lib/debug";
 
+/// Environment variable listing extra directories to search for debuginfo,
+/// separated like `PATH` is.
+const DEBUG_DIRS_VAR: &str = "BACKTRACE_DEBUG_DIRS";
+
 fn debug_path_exists() -> bool {
     cfg_if::cfg_if! {
         if #[cfg(any(target_os = "freebsd", target_os = "linux"))] {
@@ -300,34 +537,51 @@ fn debug_path_exists() -> bool {
     }
 }
 
+/// Returns the global debug directories, in the order they're searched.
+///
+/// These are the directories configured through `set_debug_dirs`, then those
+/// listed in the `BACKTRACE_DEBUG_DIRS` environment variable, and finally the
+/// system's `/usr/lib/debug`.
+fn debug_dirs() -> Vec<PathBuf> {
+    let mut dirs = unsafe { super::with_debug_dirs(|dirs| dirs.clone()) };
+    if let Some(paths) = env::var_os(DEBUG_DIRS_VAR) {
+        dirs.extend(env::split_paths(&paths).filter(|dir| dir.is_absolute()));
+    }
+    if debug_path_exists() {
+        dirs.push(PathBuf::from(OsStr::from_bytes(DEBUG_PATH)));
+    }
+    dirs
+}
+
 /// Locate a debug file based on its build ID.
 ///
+/// The file is looked for in the `.build-id` directory of each of the global
+/// debug directories.
+///
 /// The format of build id paths is documented at:
 /// https://sourceware.org/gdb/onlinedocs/gdb/Separate-Debug-Files.html
 fn locate_build_id(build_id: &[u8]) -> Option<PathBuf> {
-    const BUILD_ID_PATH: &[u8] = b"/usr/lib/debug/.build-id/";
     const BUILD_ID_SUFFIX: &[u8] = b".debug";
 
     if build_id.len() < 2 {
         return None;
     }
 
-    if !debug_path_exists() {
-        return None;
-    }
-
-    let mut path =
-        Vec::with_capacity(BUILD_ID_PATH.len() + BUILD_ID_SUFFIX.len() + build_id.len() * 2 + 1);
-    path.extend(BUILD_ID_PATH);
-    path.push(hex(build_id[0] >> 4));
-    path.push(hex(build_id[0] & 0xf));
-    path.push(b'/');
+    let mut name = Vec::with_capacity(BUILD_ID_SUFFIX.len() + build_id.len() * 2 + 1);
+    name.push(hex(build_id[0] >> 4));
+    name.push(hex(build_id[0] & 0xf));
+    name.push(b'/');
     for byte in &build_id[1..] {
-        path.push(hex(byte >> 4));
-        path.push(hex(byte & 0xf));
+        name.push(hex(byte >> 4));
+        name.push(hex(byte & 0xf));
     }
-    path.extend(BUILD_ID_SUFFIX);
-    Some(PathBuf::from(OsString::from_vec(path)))
+    name.extend(BUILD_ID_SUFFIX);
+    let name = Path::new(OsStr::from_bytes(&name));
+
+    debug_dirs()
+        .into_iter()
+        .map(|dir| dir.join(".build-id").join(name))
+        .find(|path| path.is_file())
 }
 
 fn hex(byte: u8) -> u8 {
@@ -338,57 +592,55 @@ fn hex(byte: u8) -> u8 {
     }
 }
 
-/// Locate a file specified in a `.gnu_debuglink` section.
+/// Locate the candidates for a file specified in a `.gnu_debuglink` section.
 ///
 /// `path` is the file containing the section.
 /// `filename` is from the contents of the section.
 ///
+/// All files found are returned in search order, since the first one isn't
+/// necessarily the right one: its CRC still needs to be checked.
+///
 /// Search order is based on gdb, documented at:
 /// https://sourceware.org/gdb/onlinedocs/gdb/Separate-Debug-Files.html
 ///
-/// gdb also allows the user to customize the debug search path, but we don't.
+/// Instead of gdb's customizable debug search path, the global debug
+/// directories (see `debug_dirs`) are searched.
 ///
 /// gdb also supports debuginfod, but we don't yet.
-fn locate_debuglink(path: &Path, filename: &[u8]) -> Option<PathBuf> {
-    let path = fs::canonicalize(path).ok()?;
-    let parent = path.parent()?;
-    let mut f = PathBuf::from(OsString::with_capacity(
-        DEBUG_PATH.len() + parent.as_os_str().len() + filename.len() + 2,
-    ));
+fn locate_debuglink(path: &Path, filename: &[u8]) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    let path = match fs::canonicalize(path) {
+        Ok(path) => path,
+        Err(_) => return candidates,
+    };
+    let parent = match path.parent() {
+        Some(parent) => parent,
+        None => return candidates,
+    };
     let filename = Path::new(OsStr::from_bytes(filename));
 
     // Try "/parent/filename" if it differs from "path"
-    f.push(parent);
-    f.push(filename);
+    let f = parent.join(filename);
     if f != path && f.is_file() {
-        return Some(f);
+        candidates.push(f);
     }
 
     // Try "/parent/.debug/filename"
-    let mut s = OsString::from(f);
-    s.clear();
-    f = PathBuf::from(s);
-    f.push(parent);
-    f.push(".debug");
-    f.push(filename);
+    let f = parent.join(".debug").join(filename);
     if f.is_file() {
-        return Some(f);
+        candidates.push(f);
     }
 
-    if debug_path_exists() {
-        // Try "/usr/lib/debug/parent/filename"
-        let mut s = OsString::from(f);
-        s.clear();
-        f = PathBuf::from(s);
-        f.push(OsStr::from_bytes(DEBUG_PATH));
-        f.push(parent.strip_prefix("/").unwrap());
-        f.push(filename);
+    // Try "/debugdir/parent/filename" for each debug directory, where the
+    // last one is typically "/usr/lib/debug"
+    for dir in debug_dirs() {
+        let f = dir.join(parent.strip_prefix("/").unwrap()).join(filename);
         if f.is_file() {
-            return Some(f);
+            candidates.push(f);
         }
     }
 
-    None
+    candidates
 }
 
 /// Locate a file specified in a `.gnu_debugaltlink` section.
@@ -398,9 +650,7 @@ fn locate_debuglink(path: &Path, filename: &[u8]) -> Option<PathBuf> {
 ///
 /// Search order is based on gdb:
 /// - filename, which is either absolute or relative to `path`
-/// - the build ID path under `BUILD_ID_PATH`
-///
-/// gdb also allows the user to customize the debug search path, but we don't.
+/// - the build ID path under each of the global debug directories
 ///
 /// gdb also supports debuginfod, but we don't yet.
 fn locate_debugaltlink(path: &Path, filename: &[u8], build_id: &[u8]) -> Option<PathBuf> {
@@ -421,3 +671,124 @@ fn locate_debugaltlink(path: &Path, filename: &[u8], build_id: &[u8]) -> Option<
 
     locate_build_id(build_id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{crc32, CRC32_TABLE};
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn crc32_table() {
+        for (i, &entry) in CRC32_TABLE.iter().enumerate() {
+            let mut crc = i as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 {
+                    0xedb8_8320 ^ (crc >> 1)
+                } else {
+                    crc >> 1
+                };
+            }
+            assert_eq!(entry, crc, "entry {}", i);
+        }
+    }
+
+    #[cfg(feature = "ruzstd")]
+    mod zstd {
+        use super::super::Vec;
+
+        // "Hello, " in a frame of a single raw block without a checksum, as
+        // written by `zstd --no-check`.
+        const HELLO: &[u8] = &[
+            0x28, 0xb5, 0x2f, 0xfd, 0x00, 0x58, 0x39, 0x00, 0x00, 0x48, 0x65, 0x6c, 0x6c, 0x6f,
+            0x2c, 0x20,
+        ];
+
+        // "debuginfo " eight times over in a frame of a single compressed
+        // block with a checksum, as written by `zstd -19`.
+        const DEBUGINFO: &[u8] = &[
+            0x28, 0xb5, 0x2f, 0xfd, 0x04, 0x68, 0x95, 0x00, 0x00, 0x58, 0x64, 0x65, 0x62, 0x75,
+            0x67, 0x69, 0x6e, 0x66, 0x6f, 0x20, 0x64, 0x01, 0x00, 0x52, 0xb4, 0x78, 0x01, 0x7f,
+            0xc7, 0x0c, 0xaa,
+        ];
+
+        // A skippable frame carrying three bytes of its own.
+        const SKIPPABLE: &[u8] = &[
+            0x50, 0x2a, 0x4d, 0x18, 0x03, 0x00, 0x00, 0x00, b'a', b'b', b'c',
+        ];
+
+        fn decompress(frames: &[&[u8]], len: usize) -> Option<Vec<u8>> {
+            let input = frames.concat();
+            let mut output = vec![0; len];
+            super::super::decompress_zstd(&input, &mut output)?;
+            Some(output)
+        }
+
+        #[test]
+        fn frames() {
+            let debuginfo = b"debuginfo ".repeat(8);
+            assert_eq!(decompress(&[HELLO], 7).unwrap(), b"Hello, ");
+            assert_eq!(decompress(&[DEBUGINFO], 80).unwrap(), debuginfo);
+
+            let expected = [&b"Hello, "[..], &debuginfo].concat();
+            let frames = [HELLO, SKIPPABLE, DEBUGINFO];
+            assert_eq!(decompress(&frames, 87).unwrap(), expected);
+            let frames = [SKIPPABLE, HELLO, DEBUGINFO, SKIPPABLE];
+            assert_eq!(decompress(&frames, 87).unwrap(), expected);
+        }
+
+        #[test]
+        fn mismatches() {
+            // The output has to be filled exactly.
+            assert!(decompress(&[HELLO], 6).is_none());
+            assert!(decompress(&[HELLO, DEBUGINFO], 86).is_none());
+            assert!(decompress(&[HELLO, DEBUGINFO], 88).is_none());
+
+            // Truncated frames, including skippable ones, are errors, and so
+            // is anything that isn't a frame at all.
+            assert!(decompress(&[&DEBUGINFO[..20]], 80).is_none());
+            assert!(decompress(&[HELLO, &SKIPPABLE[..10]], 7).is_none());
+            assert!(decompress(&[b"not zstd"], 8).is_none());
+        }
+    }
+
+    #[cfg(feature = "lzma-rs")]
+    mod xz {
+        use super::super::xz_decompressed_size;
+
+        // "minidebuginfo " twelve times over in a stream of a single block
+        // with a CRC-32 check, as written by `xz -9 --check=crc32`.
+        const MINIDEBUGINFO: &[u8] = &[
+            0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00, 0x00, 0x01, 0x69, 0x22, 0xde, 0x36, 0x04, 0xc0,
+            0x1d, 0xa8, 0x01, 0x21, 0x01, 0x1c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x8f, 0xbb, 0x28, 0x5d, 0xe0, 0x00, 0xa7, 0x00, 0x15, 0x5d, 0x00, 0x36, 0x9a, 0x4a,
+            0x1f, 0x65, 0x97, 0x4d, 0xdd, 0xb3, 0x1c, 0x48, 0x5c, 0x6a, 0xc3, 0x34, 0x4d, 0x6a,
+            0x34, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1b, 0xb3, 0xb9, 0x7a, 0x00, 0x01,
+            0x35, 0xa8, 0x01, 0x00, 0x00, 0x00, 0xf1, 0xf1, 0x09, 0x6f, 0x3e, 0x30, 0x0d, 0x8b,
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x01, 0x59, 0x5a,
+        ];
+
+        #[test]
+        fn decompressed_size() {
+            let expected = b"minidebuginfo ".repeat(12);
+            assert_eq!(xz_decompressed_size(MINIDEBUGINFO), Some(expected.len()));
+
+            // The size is enough to decompress straight into a slice.
+            let mut output = vec![0; expected.len()];
+            let mut rest = &mut output[..];
+            lzma_rs::xz_decompress(&mut &MINIDEBUGINFO[..], &mut rest).unwrap();
+            assert!(rest.is_empty());
+            assert_eq!(output, expected);
+
+            // Without its footer, or its index, the size isn't known.
+            let len = MINIDEBUGINFO.len();
+            assert_eq!(xz_decompressed_size(&MINIDEBUGINFO[..len - 1]), None);
+            assert_eq!(xz_decompressed_size(&MINIDEBUGINFO[len - 14..]), None);
+            assert_eq!(xz_decompressed_size(b"not xz"), None);
+        }
+    }
+}
@@ -17,6 +17,56 @@ pub(super) fn native_libraries() -> Vec<Library> {
     return ret;
 }
 
+/// Returns a number which changes whenever a library is loaded or unloaded,
+/// if the platform keeps track of that.
+///
+/// This reads the `dlpi_adds` and `dlpi_subs` counters, which are the same
+/// for every object visited so only the first one is looked at.
+pub(super) fn libraries_generation() -> Option<u64> {
+    cfg_if::cfg_if! {
+        if #[cfg(any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "fuchsia",
+        ))] {
+            let mut generation = None;
+            unsafe {
+                libc::dl_iterate_phdr(
+                    Some(generation_callback),
+                    &mut generation as *mut Option<u64> as *mut _,
+                );
+            }
+            generation
+        } else {
+            None
+        }
+    }
+}
+
+// `info` should be a valid pointers.
+// `generation` should be a valid pointer to an `Option<u64>`.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "fuchsia",
+))]
+unsafe extern "C" fn generation_callback(
+    info: *mut libc::dl_phdr_info,
+    size: libc::size_t,
+    generation: *mut libc::c_void,
+) -> libc::c_int {
+    let info = &*info;
+    // The counters were added to the end of `dl_phdr_info` later on, so they
+    // may not be there at all.
+    let end = (&info.dlpi_subs as *const _ as usize) + core::mem::size_of_val(&info.dlpi_subs);
+    if size >= end - (info as *const _ as usize) {
+        *(generation as *mut Option<u64>) = Some(info.dlpi_adds.wrapping_add(info.dlpi_subs));
+    }
+    1
+}
+
 fn infer_current_exe(base_addr: usize) -> OsString {
     if let Ok(entries) = super::parse_running_mmaps::parse_maps() {
         let opt_path = entries
@@ -56,8 +106,31 @@ unsafe extern "C" fn callback(
         OsStr::from_bytes(bytes).to_owned()
     };
     let headers = slice::from_raw_parts(info.dlpi_phdr, info.dlpi_phnum as usize);
+    // Notes are only read if they're covered by a loaded segment, since
+    // otherwise nothing guarantees they're actually mapped into memory.
+    let is_loaded = |vaddr: usize, len: usize| {
+        headers.iter().any(|load| {
+            let start = load.p_vaddr as usize;
+            let end = start.wrapping_add(load.p_filesz as usize);
+            load.p_type == object::elf::PT_LOAD && start <= vaddr && vaddr.wrapping_add(len) <= end
+        })
+    };
+    let build_id = headers
+        .iter()
+        .filter(|header| header.p_type == object::elf::PT_NOTE)
+        .filter(|header| is_loaded(header.p_vaddr as usize, header.p_memsz as usize))
+        .filter_map(|header| {
+            let notes = slice::from_raw_parts(
+                (info.dlpi_addr as usize).wrapping_add(header.p_vaddr as usize) as *const u8,
+                header.p_memsz as usize,
+            );
+            gnu_build_id(notes, header.p_align as usize)
+        })
+        .next();
     libs.push(Library {
         name,
+        build_id,
+        file_id: None,
         segments: headers
             .iter()
             .map(|header| LibrarySegment {
@@ -69,3 +142,35 @@ unsafe extern "C" fn callback(
     });
     0
 }
+
+// Searches the contents of a `PT_NOTE` segment for the GNU build ID note.
+//
+// The layout of notes is documented in the "Note Section" part of the ELF
+// gABI: each note is a header of three words followed by its name and its
+// descriptor, each padded to the alignment of the segment.
+pub(super) fn gnu_build_id(mut notes: &[u8], align: usize) -> Option<Vec<u8>> {
+    let align = if align == 8 { 8 } else { 4 };
+    let pad = |len: usize| (len + align - 1) & !(align - 1);
+    let word = |bytes: &[u8], i: usize| -> Option<usize> {
+        let bytes = bytes.get(i * 4..i * 4 + 4)?;
+        Some(u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    };
+    while notes.len() >= 12 {
+        let namesz = word(notes, 0)?;
+        let descsz = word(notes, 1)?;
+        let n_type = word(notes, 2)? as u32;
+        // The stored name includes its nul terminator, unlike the constant.
+        let name = notes.get(12..12usize.checked_add(namesz)?)?;
+        let name = match name.split_last() {
+            Some((0, name)) => name,
+            _ => name,
+        };
+        let desc_start = pad(12 + namesz);
+        let desc = notes.get(desc_start..desc_start.checked_add(descsz)?)?;
+        if name == object::elf::ELF_NOTE_GNU && n_type == object::elf::NT_GNU_BUILD_ID {
+            return Some(desc.to_vec());
+        }
+        notes = notes.get(pad(desc_start + descsz)..)?;
+    }
+    None
+}
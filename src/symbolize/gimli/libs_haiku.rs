@@ -36,6 +36,8 @@ pub(super) fn native_libraries() -> Vec<Library> {
             let name = OsStr::from_bytes(bytes).to_owned();
             libraries.push(Library {
                 name: name,
+                build_id: None,
+                file_id: None,
                 segments: segments,
                 bias: info.text as usize,
             });
@@ -71,6 +71,8 @@ pub(super) fn native_libraries() -> Vec<Library> {
 
         libs.push(Library {
             name,
+            build_id: None,
+            file_id: None,
             segments: phdr
                 .iter()
                 .map(|p| {
@@ -19,6 +19,8 @@ pub(super) fn native_libraries() -> Vec<Library> {
     let path = "romfs:/debug_info.elf";
     ret.push(Library {
         name: path.into(),
+        build_id: None,
+        file_id: None,
         segments,
         bias,
     });
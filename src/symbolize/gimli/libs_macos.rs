@@ -68,7 +68,11 @@ fn native_library(i: u32) -> Option<Library> {
     let mut segments = Vec::new();
     let mut first_text = 0;
     let mut text_fileoff_zero = false;
+    let mut uuid = None;
     while let Some(cmd) = load_commands.next().ok()? {
+        if let Some(uuid_cmd) = cmd.uuid().ok()? {
+            uuid = Some(uuid_cmd.uuid.to_vec());
+        }
         if let Some((seg, _)) = cmd.segment_32().ok()? {
             if seg.name() == b"__TEXT" {
                 first_text = segments.len();
@@ -140,6 +144,8 @@ fn native_library(i: u32) -> Option<Library> {
 
     Some(Library {
         name: OsStr::from_bytes(name.to_bytes()).to_owned(),
+        build_id: uuid,
+        file_id: None,
         segments,
         bias: slide,
     })
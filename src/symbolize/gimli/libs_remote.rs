@@ -0,0 +1,139 @@
+// Finding the libraries loaded into another process on Linux. The files mapped
+// into a running process are listed in `/proc/<pid>/maps` (and those mapped
+// into a dumped one in the core file), and the mapping of the start of each one
+// holds its ELF header, which leads to its program headers.
+
+use super::mystd::ffi::{OsStr, OsString};
+#[cfg(feature = "ptrace")]
+use super::mystd::path::Path;
+#[cfg(feature = "ptrace")]
+use super::parse_running_mmaps::parse_maps_at;
+use super::{Library, LibrarySegment, Vec};
+use core::mem;
+use object::elf::{PT_LOAD, PT_NOTE};
+use object::read::elf::{FileHeader, ProgramHeader};
+use object::NativeEndian;
+
+type Elf = object::elf::FileHeader64<NativeEndian>;
+
+// Anything larger than this is assumed to be garbage rather than headers or
+// notes worth reading out of the other process.
+const MAX_READ: usize = 64 * 1024;
+
+/// Returns the libraries loaded into the process `pid`, using `read` to read
+/// its memory. `read` fills the buffer it's given with the memory at the
+/// address it's given, and returns whether it was able to.
+#[cfg(feature = "ptrace")]
+pub(super) fn remote_libraries(
+    pid: u32,
+    read: &mut dyn FnMut(usize, &mut [u8]) -> bool,
+) -> Vec<Library> {
+    let path = format!("/proc/{}/maps", pid);
+    let entries = match parse_maps_at(Path::new(&path)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .iter()
+        .filter(|entry| entry.offset() == 0 && !entry.pathname().is_empty())
+        .filter_map(|entry| {
+            let (start, end) = entry.address();
+            library(start, end, entry.pathname(), read)
+        })
+        .collect()
+}
+
+/// Returns the libraries loaded at `mappings`, the start and end of each
+/// mapping of the start of a file along with the path of the file, using
+/// `read` to read the memory there as for `remote_libraries`.
+#[cfg(feature = "coredump")]
+pub(super) fn mapped_libraries(
+    mappings: &[(usize, usize, &OsStr)],
+    read: &mut dyn FnMut(usize, &mut [u8]) -> bool,
+) -> Vec<Library> {
+    mappings
+        .iter()
+        .filter_map(|&(start, end, path)| library(start, end, path, read))
+        .collect()
+}
+
+fn library(
+    start: usize,
+    end: usize,
+    path: &OsStr,
+    read: &mut dyn FnMut(usize, &mut [u8]) -> bool,
+) -> Option<Library> {
+    let mut header = vec![0; mem::size_of::<Elf>()];
+    if !read(start, &mut header) {
+        return None;
+    }
+    // Plenty of files which aren't ELF objects get mapped as well, and those
+    // are weeded out here.
+    let elf = Elf::parse(&header[..]).ok()?;
+    let endian = elf.endian().ok()?;
+    let headers_len = (elf.e_phnum(endian) as usize)
+        .checked_mul(elf.e_phentsize(endian) as usize)?
+        .checked_add(elf.e_phoff(endian) as usize)?;
+    if headers_len > MAX_READ || headers_len > end - start {
+        return None;
+    }
+    let mut data = vec![0; headers_len];
+    if !read(start, &mut data) {
+        return None;
+    }
+    let headers = Elf::parse(&data[..])
+        .ok()?
+        .program_headers(endian, &data[..])
+        .ok()?;
+
+    // The mapping of the start of the file is the segment loaded from offset
+    // zero, which gives away where the whole file was loaded.
+    let first = headers
+        .iter()
+        .filter(|header| header.p_type(endian) == PT_LOAD)
+        .min_by_key(|header| header.p_offset(endian))?;
+    let stated_start = first.p_vaddr(endian).wrapping_sub(first.p_offset(endian));
+    let bias = start.wrapping_sub(stated_start as usize);
+
+    // Notes are only read if they're covered by a loaded segment, since
+    // otherwise nothing guarantees they're actually mapped into memory.
+    let is_loaded = |vaddr: u64, len: u64| {
+        headers.iter().any(|load| {
+            let start = load.p_vaddr(endian);
+            let end = start.wrapping_add(load.p_filesz(endian));
+            load.p_type(endian) == PT_LOAD && start <= vaddr && vaddr.wrapping_add(len) <= end
+        })
+    };
+    let build_id = headers
+        .iter()
+        .filter(|header| header.p_type(endian) == PT_NOTE)
+        .filter(|header| is_loaded(header.p_vaddr(endian), header.p_memsz(endian)))
+        .filter_map(|header| {
+            let len = header.p_memsz(endian) as usize;
+            if len > MAX_READ {
+                return None;
+            }
+            let mut notes = vec![0; len];
+            let addr = bias.wrapping_add(header.p_vaddr(endian) as usize);
+            if !read(addr, &mut notes) {
+                return None;
+            }
+            super::libs_dl_iterate_phdr::gnu_build_id(&notes, header.p_align(endian) as usize)
+        })
+        .next();
+
+    Some(Library {
+        name: OsString::from(path),
+        build_id,
+        file_id: None,
+        segments: headers
+            .iter()
+            .filter(|header| header.p_type(endian) == PT_LOAD)
+            .map(|header| LibrarySegment {
+                len: header.p_memsz(endian) as usize,
+                stated_virtual_memory_address: header.p_vaddr(endian) as usize,
+            })
+            .collect(),
+        bias,
+    })
+}
@@ -1,51 +1,24 @@
 use super::super::super::windows::*;
+use super::super::toolhelp;
 use super::mystd::os::windows::prelude::*;
-use super::{coff, mmap, Library, LibrarySegment, OsString};
+use super::{mmap, Library, LibrarySegment, OsString};
 use alloc::vec;
 use alloc::vec::Vec;
-use core::mem;
-use core::mem::MaybeUninit;
 
-// For loading native libraries on Windows, see some discussion on
-// rust-lang/rust#71060 for the various strategies here.
 pub(super) fn native_libraries() -> Vec<Library> {
     let mut ret = Vec::new();
     unsafe {
-        add_loaded_images(&mut ret);
-    }
-    return ret;
-}
-
-unsafe fn add_loaded_images(ret: &mut Vec<Library>) {
-    let snap = CreateToolhelp32Snapshot(TH32CS_SNAPMODULE, 0);
-    if snap == INVALID_HANDLE_VALUE {
-        return;
-    }
-
-    let mut me = MaybeUninit::<MODULEENTRY32W>::zeroed().assume_init();
-    me.dwSize = mem::size_of_val(&me) as DWORD;
-    if Module32FirstW(snap, &mut me) == TRUE {
-        loop {
-            if let Some(lib) = load_library(&me) {
+        toolhelp::for_each_loaded_image(|me| {
+            if let Some(lib) = load_library(me) {
                 ret.push(lib);
             }
-
-            if Module32NextW(snap, &mut me) != TRUE {
-                break;
-            }
-        }
+        });
     }
-
-    CloseHandle(snap);
+    return ret;
 }
 
 unsafe fn load_library(me: &MODULEENTRY32W) -> Option<Library> {
-    let pos = me
-        .szExePath
-        .iter()
-        .position(|i| *i == 0)
-        .unwrap_or(me.szExePath.len());
-    let name = OsString::from_wide(&me.szExePath[..pos]);
+    let name = OsString::from_wide(toolhelp::image_path(me));
 
     // MinGW libraries currently don't support ASLR
     // (rust-lang/rust#16514), but DLLs can still be relocated around in
@@ -76,10 +49,12 @@ unsafe fn load_library(me: &MODULEENTRY32W) -> Option<Library> {
     // For now it appears that unlike ELF/MachO we can make do with one
     // segment per library, using `modBaseSize` as the whole size.
     let mmap = mmap(name.as_ref())?;
-    let image_base = coff::get_image_base(&mmap)?;
+    let image_base = toolhelp::image_base(&mmap)?;
     let base_addr = me.modBaseAddr as usize;
     Some(Library {
         name,
+        build_id: toolhelp::codeview_id(me),
+        file_id: None,
         bias: base_addr.wrapping_sub(image_base),
         segments: vec![LibrarySegment {
             stated_virtual_memory_address: image_base,
@@ -1,4 +1,4 @@
-use super::{Box, Context, Mapping, Path, Stash, Vec};
+use super::{Box, Context, Mapping, Path, Stash, SymtabSymbol, Vec};
 use core::convert::TryInto;
 use object::macho;
 use object::read::macho::{MachHeader, Nlist, Section, Segment as _};
@@ -166,6 +166,7 @@ pub struct Object<'a> {
     object_map: Option<object::ObjectMap<'a>>,
     // The outer Option is for lazy loading, and the inner Option allows load errors to be cached.
     object_mappings: Box<[Option<Option<Mapping>>]>,
+    uuid: Option<[u8; 16]>,
 }
 
 impl<'a> Object<'a> {
@@ -219,6 +220,7 @@ impl<'a> Object<'a> {
             syms_sort_by_name,
             object_map,
             object_mappings: object_mappings.into_boxed_slice(),
+            uuid: mach.uuid(endian, data, 0).ok().and_then(|uuid| uuid),
         })
     }
 
@@ -236,14 +238,22 @@ impl<'a> Object<'a> {
         Some(section.data(self.endian, self.data).ok()?)
     }
 
-    pub fn search_symtab<'b>(&'b self, addr: u64) -> Option<&'b [u8]> {
+    pub fn search_symtab<'b>(&'b self, addr: u64) -> Option<SymtabSymbol<'b>> {
         debug_assert!(!self.syms_sort_by_name);
         let i = match self.syms.binary_search_by_key(&addr, |(_, addr)| *addr) {
             Ok(i) => i,
             Err(i) => i.checked_sub(1)?,
         };
-        let (sym, _addr) = self.syms.get(i)?;
-        Some(sym)
+        let &(name, address) = self.syms.get(i)?;
+        Some(SymtabSymbol {
+            name,
+            address,
+            size: None,
+        })
+    }
+
+    pub(super) fn build_id(&self) -> Option<&[u8]> {
+        self.uuid.as_ref().map(|uuid| &uuid[..])
     }
 
     /// Try to load a context for an object file.
@@ -4,6 +4,7 @@
 
 use super::mystd::fs::File;
 use super::mystd::io::Read;
+use super::mystd::path::Path;
 use super::mystd::str::FromStr;
 use super::{OsString, String, Vec};
 
@@ -54,13 +55,18 @@ pub(super) struct MapsEntry {
 }
 
 pub(super) fn parse_maps() -> Result<Vec<MapsEntry>, &'static str> {
+    parse_maps_at(Path::new("/proc/self/maps"))
+}
+
+/// Parses a file in the format of `/proc/self/maps`, such as the maps of
+/// another process in `/proc/<pid>/maps`.
+pub(super) fn parse_maps_at(path: &Path) -> Result<Vec<MapsEntry>, &'static str> {
     let mut v = Vec::new();
-    let mut proc_self_maps =
-        File::open("/proc/self/maps").map_err(|_| "Couldn't open /proc/self/maps")?;
+    let mut maps = File::open(path).map_err(|_| "Couldn't open maps")?;
     let mut buf = String::new();
-    let _bytes_read = proc_self_maps
+    let _bytes_read = maps
         .read_to_string(&mut buf)
-        .map_err(|_| "Couldn't read /proc/self/maps")?;
+        .map_err(|_| "Couldn't read maps")?;
     for line in buf.lines() {
         v.push(line.parse()?);
     }
@@ -76,6 +82,16 @@ impl MapsEntry {
     pub(super) fn ip_matches(&self, ip: usize) -> bool {
         self.address.0 <= ip && ip < self.address.1
     }
+
+    #[cfg(feature = "ptrace")]
+    pub(super) fn address(&self) -> (usize, usize) {
+        self.address
+    }
+
+    #[cfg(feature = "ptrace")]
+    pub(super) fn offset(&self) -> usize {
+        self.offset
+    }
 }
 
 impl FromStr for MapsEntry {
@@ -0,0 +1,439 @@
+//! Support for split DWARF, as generated by `-gsplit-dwarf` or by rustc's
+//! `-Csplit-debuginfo=unpacked` and `-Csplit-debuginfo=packed` on Linux.
+//!
+//! With split DWARF, the object only keeps a skeleton unit for each
+//! compilation unit, with its address ranges and line table but none of its
+//! functions. The rest of the unit is moved into a `.dwo` file, named by the
+//! skeleton unit relative to its compilation directory, or into a `.dwp`
+//! package of all of them next to the object. Either way the split unit is
+//! found through the DWO id it shares with the skeleton unit.
+//!
+//! `addr2line` only sees the skeleton units, so the functions at an address,
+//! including the ones inlined into each other, are looked up here.
+
+// only used for ELF objects, so allow dead code elsewhere
+#![cfg_attr(
+    any(
+        windows,
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "tvos",
+        target_os = "watchos",
+    ),
+    allow(dead_code)
+)]
+
+use super::gimli;
+use super::mystd::ffi::OsString;
+use super::mystd::path::{Path, PathBuf};
+use super::{find_subprogram, subprograms, Subprogram};
+use super::{Endian, EndianSlice, Stash, String, Vec};
+use core::mem;
+use core::str;
+use object::{Object as _, ObjectSection as _};
+
+type Slice<'a> = EndianSlice<'a, Endian>;
+
+/// The split units which the skeleton units of an object point to, loaded as
+/// they're needed.
+pub(super) struct SplitDwarf<'a> {
+    /// The path of the object, next to which the `.dwp` package is looked for,
+    /// or `None` if split units aren't looked for at all.
+    path: Option<PathBuf>,
+    /// The `.dwp` package, once it's been looked for.
+    package: Option<Option<gimli::DwarfPackage<Slice<'a>>>>,
+    /// The split units loaded so far, sorted by their DWO id, or `None` for
+    /// the ones that couldn't be found.
+    units: Vec<(u64, Option<SplitUnit<'a>>)>,
+    // Holds the mapped `.dwo` files and `.dwp` package which everything above
+    // borrows from, so it has to be declared, and dropped, last.
+    stash: Stash,
+}
+
+/// A split compilation unit, along with the sections it's in.
+pub(super) struct SplitUnit<'a> {
+    dwarf: gimli::Dwarf<Slice<'a>>,
+    unit: gimli::Unit<Slice<'a>>,
+    /// The address ranges of the subprograms of the unit, sorted by address.
+    functions: Vec<Subprogram>,
+    /// The paths of the files in the file table of the unit, which
+    /// `DW_AT_call_file` is an index into.
+    files: Vec<Option<String>>,
+}
+
+impl<'a> SplitDwarf<'a> {
+    /// Looks for the split units of the object at `path`, or not at all if
+    /// `path` is `None`.
+    pub(super) fn new(path: Option<&Path>) -> SplitDwarf<'a> {
+        SplitDwarf {
+            path: path.map(Path::to_path_buf),
+            package: None,
+            units: Vec::new(),
+            stash: Stash::new(),
+        }
+    }
+
+    /// Returns the split unit which the skeleton unit containing `addr` points
+    /// to, loading it if that hasn't been tried before.
+    pub(super) fn find_unit(
+        &mut self,
+        parent: &addr2line::Context<Slice<'a>>,
+        addr: u64,
+    ) -> Option<&SplitUnit<'a>> {
+        self.path.as_ref()?;
+        let skeleton = parent.find_dwarf_unit(addr)?;
+        let dwo_id = skeleton.dwo_id?;
+        let i = match self.units.binary_search_by_key(&dwo_id.0, |(id, _)| *id) {
+            Ok(i) => i,
+            Err(i) => {
+                let unit = self.load(parent.dwarf(), skeleton, dwo_id);
+                self.units.insert(i, (dwo_id.0, unit));
+                i
+            }
+        };
+        self.units[i].1.as_ref()
+    }
+
+    fn load(
+        &mut self,
+        parent: &gimli::Dwarf<Slice<'a>>,
+        skeleton: &gimli::Unit<Slice<'a>>,
+        dwo_id: gimli::DwoId,
+    ) -> Option<SplitUnit<'a>> {
+        let packaged = self
+            .package()
+            .and_then(|package| package.find_cu(dwo_id, parent).ok()?);
+        let dwarf = match packaged {
+            Some(dwarf) => dwarf,
+            None => self.load_dwo(parent, skeleton)?,
+        };
+        SplitUnit::new(dwarf, parent, skeleton, dwo_id)
+    }
+
+    /// Returns the `.dwp` package next to the object, named after it with
+    /// `.dwp` appended, looking for it if that hasn't been done before.
+    fn package(&mut self) -> Option<&gimli::DwarfPackage<Slice<'a>>> {
+        if self.package.is_none() {
+            let package = self.path.as_ref().and_then(|path| {
+                let mut name = OsString::from(path.as_os_str());
+                name.push(".dwp");
+                let file = object::File::parse(self.map(Path::new(&name))?).ok()?;
+                let empty = EndianSlice::new(&[], Endian);
+                gimli::DwarfPackage::load(
+                    |id| -> Result<_, gimli::Error> {
+                        Ok(EndianSlice::new(section(&file, id.dwo_name()), Endian))
+                    },
+                    empty,
+                )
+                .ok()
+            });
+            self.package = Some(package);
+        }
+        self.package.as_ref()?.as_ref()
+    }
+
+    /// Loads the `.dwo` file which the skeleton unit `skeleton` names.
+    ///
+    /// The name is relative to the compilation directory of the unit, but the
+    /// file is also looked for next to the object, in case it was moved along
+    /// with the object.
+    fn load_dwo(
+        &self,
+        parent: &gimli::Dwarf<Slice<'a>>,
+        skeleton: &gimli::Unit<Slice<'a>>,
+    ) -> Option<gimli::Dwarf<Slice<'a>>> {
+        let name = parent
+            .attr_string(skeleton, skeleton.dwo_name().ok()??)
+            .ok()?;
+        let name = Path::new(str::from_utf8(name.slice()).ok()?);
+        let mut paths = Vec::new();
+        match skeleton
+            .comp_dir
+            .and_then(|dir| str::from_utf8(dir.slice()).ok())
+        {
+            Some(dir) => paths.push(Path::new(dir).join(name)),
+            None => paths.push(name.to_path_buf()),
+        }
+        if let (Some(dir), Some(file_name)) = (
+            self.path.as_ref().and_then(|path| path.parent()),
+            name.file_name(),
+        ) {
+            paths.push(dir.join(file_name));
+        }
+
+        let data = paths.iter().filter_map(|path| self.map(path)).next()?;
+        let file = object::File::parse(data).ok()?;
+        let mut dwarf = gimli::Dwarf::load(|id| -> Result<_, ()> {
+            Ok(EndianSlice::new(section(&file, id.dwo_name()), Endian))
+        })
+        .ok()?;
+        dwarf.make_dwo(parent);
+        Some(dwarf)
+    }
+
+    /// Maps the file at `path` for as long as `self` lives.
+    fn map(&self, path: &Path) -> Option<&'a [u8]> {
+        let data = self.stash.cache_mmap(super::mmap(path)?);
+        // SAFETY: the stash is only dropped along with `self`, after anything
+        // borrowing from it, and nothing borrowing from it is handed out for
+        // longer than `self` is borrowed for. Moving the stash around doesn't
+        // move the memory it maps either.
+        Some(unsafe { mem::transmute::<&[u8], &'a [u8]>(data) })
+    }
+}
+
+/// Returns the data of the section `name` of `file`, or an empty slice if
+/// there's no such section.
+fn section<'a>(file: &object::File<'a>, name: Option<&str>) -> &'a [u8] {
+    name.and_then(|name| file.section_by_name(name))
+        .and_then(|section| section.data().ok())
+        .unwrap_or(&[])
+}
+
+impl<'a> SplitUnit<'a> {
+    fn new(
+        dwarf: gimli::Dwarf<Slice<'a>>,
+        parent: &gimli::Dwarf<Slice<'a>>,
+        skeleton: &gimli::Unit<Slice<'a>>,
+        dwo_id: gimli::DwoId,
+    ) -> Option<SplitUnit<'a>> {
+        let mut headers = dwarf.units();
+        let mut unit = loop {
+            let unit = gimli::Unit::new(&dwarf, headers.next().ok()??).ok()?;
+            if unit.dwo_id == Some(dwo_id) {
+                break unit;
+            }
+        };
+        // The split unit refers to addresses and ranges relative to the bases
+        // given in the skeleton unit.
+        unit.copy_relocated_attributes(skeleton);
+
+        let functions = subprograms(&dwarf, &unit);
+
+        // Split units don't have a `DW_AT_stmt_list`. With DWARF 5 their file
+        // table is at the start of `.debug_line.dwo`, but with the GNU
+        // extension to DWARF 4 they share the one of the skeleton unit.
+        let program = dwarf.debug_line.program(
+            gimli::DebugLineOffset(0),
+            unit.header.address_size(),
+            unit.comp_dir,
+            unit.name,
+        );
+        let files = match program {
+            Ok(program) => files(&dwarf, &unit, program.header()),
+            Err(_) => match skeleton.line_program {
+                Some(ref program) => files(parent, skeleton, program.header()),
+                None => Vec::new(),
+            },
+        };
+
+        Some(SplitUnit {
+            dwarf,
+            unit,
+            functions,
+            files,
+        })
+    }
+
+    /// Returns the stated virtual memory address and the size of the function
+    /// which `addr` is in.
+    pub(super) fn function(&self, addr: u64) -> Option<(u64, u64)> {
+        self.subprogram(addr)
+            .map(|&(begin, end, _)| (begin, end - begin))
+    }
+
+    fn subprogram(&self, addr: u64) -> Option<&Subprogram> {
+        find_subprogram(&self.functions, addr)
+    }
+
+    /// Returns the names of the functions at `addr` along with where in each
+    /// of them `addr` is, innermost inlined function first, the same as
+    /// `addr2line::FrameIter` would.
+    ///
+    /// `location` is the location of `addr` itself, as found in the line table
+    /// of the skeleton unit.
+    pub(super) fn frames<'s>(
+        &'s self,
+        addr: u64,
+        location: Option<addr2line::Location<'s>>,
+    ) -> Vec<(Option<&'s [u8]>, Option<addr2line::Location<'s>>)> {
+        let mut chain = Vec::new();
+        let mut offset = match self.subprogram(addr) {
+            Some(&(_, _, offset)) => offset,
+            None => return Vec::new(),
+        };
+        loop {
+            chain.push(offset);
+            let mut tree = match self.unit.entries_tree(Some(offset)) {
+                Ok(tree) => tree,
+                Err(_) => break,
+            };
+            let inlined = match tree.root() {
+                Ok(root) => self.find_inlined(root.children(), addr),
+                Err(_) => None,
+            };
+            match inlined {
+                Some(inlined) => offset = inlined,
+                None => break,
+            }
+        }
+
+        let mut frames = Vec::new();
+        let mut location = location;
+        for &offset in chain.iter().rev() {
+            let entry = match self.unit.entry(offset) {
+                Ok(entry) => entry,
+                Err(_) => break,
+            };
+            frames.push((self.name(&entry, 16), location));
+            location = self.call_location(&entry);
+        }
+        frames
+    }
+
+    /// Returns the offset of the `DW_TAG_inlined_subroutine` among `children`
+    /// which contains `addr`, looking through lexical blocks.
+    fn find_inlined(
+        &self,
+        mut children: gimli::EntriesTreeIter<'_, '_, '_, Slice<'a>>,
+        addr: u64,
+    ) -> Option<gimli::UnitOffset> {
+        while let Ok(Some(child)) = children.next() {
+            let entry = child.entry();
+            match entry.tag() {
+                gimli::DW_TAG_inlined_subroutine if self.contains(entry, addr) => {
+                    return Some(entry.offset());
+                }
+                gimli::DW_TAG_lexical_block => {
+                    if let Some(offset) = self.find_inlined(child.children(), addr) {
+                        return Some(offset);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn contains(
+        &self,
+        entry: &gimli::DebuggingInformationEntry<'_, '_, Slice<'a>>,
+        addr: u64,
+    ) -> bool {
+        let mut ranges = match self.dwarf.die_ranges(&self.unit, entry) {
+            Ok(ranges) => ranges,
+            Err(_) => return false,
+        };
+        while let Ok(Some(range)) = ranges.next() {
+            if range.begin <= addr && addr < range.end {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns the name of the function `entry`, preferring its linkage name,
+    /// and following `DW_AT_abstract_origin` and `DW_AT_specification` up to
+    /// `depth` times to find one.
+    fn name(
+        &self,
+        entry: &gimli::DebuggingInformationEntry<'_, '_, Slice<'a>>,
+        depth: usize,
+    ) -> Option<&'a [u8]> {
+        let mut name = None;
+        let mut next = None;
+        let mut attrs = entry.attrs();
+        while let Ok(Some(attr)) = attrs.next() {
+            match attr.name() {
+                gimli::DW_AT_linkage_name | gimli::DW_AT_MIPS_linkage_name => {
+                    if let Ok(linkage_name) = self.dwarf.attr_string(&self.unit, attr.value()) {
+                        return Some(linkage_name.slice());
+                    }
+                }
+                gimli::DW_AT_name => {
+                    name = self.dwarf.attr_string(&self.unit, attr.value()).ok();
+                }
+                gimli::DW_AT_abstract_origin | gimli::DW_AT_specification => {
+                    next = Some(attr.value());
+                }
+                _ => {}
+            }
+        }
+        if let Some(name) = name {
+            return Some(name.slice());
+        }
+        match next {
+            Some(gimli::AttributeValue::UnitRef(offset)) if depth > 0 => {
+                let entry = self.unit.entry(offset).ok()?;
+                self.name(&entry, depth - 1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns where the inlined function `entry` was inlined.
+    fn call_location(
+        &self,
+        entry: &gimli::DebuggingInformationEntry<'_, '_, Slice<'a>>,
+    ) -> Option<addr2line::Location<'_>> {
+        let udata = |name| entry.attr_value(name).ok()??.udata_value();
+        let file = match entry.attr_value(gimli::DW_AT_call_file) {
+            Ok(Some(gimli::AttributeValue::FileIndex(i))) => self.files.get(i as usize),
+            _ => None,
+        };
+        let file = file.and_then(|file| file.as_ref()).map(|file| &file[..]);
+        let line = udata(gimli::DW_AT_call_line).filter(|&line| line != 0);
+        let column = udata(gimli::DW_AT_call_column).filter(|&column| column != 0);
+        Some(addr2line::Location {
+            file,
+            line: line.map(|line| line as u32),
+            column: column.map(|column| column as u32),
+        })
+    }
+}
+
+/// Returns the paths of the files in the file table `header` of `unit`, by
+/// their index.
+fn files<'a>(
+    dwarf: &gimli::Dwarf<Slice<'a>>,
+    unit: &gimli::Unit<Slice<'a>>,
+    header: &gimli::LineProgramHeader<Slice<'a>>,
+) -> Vec<Option<String>> {
+    (0..=header.file_names().len() as u64)
+        .map(|i| render_file(dwarf, unit, header.file(i)?, header))
+        .collect()
+}
+
+/// Returns the path of `file`, joined onto its directory and the compilation
+/// directory of `unit` the same way `addr2line` does.
+fn render_file<'a>(
+    dwarf: &gimli::Dwarf<Slice<'a>>,
+    unit: &gimli::Unit<Slice<'a>>,
+    file: &gimli::FileEntry<Slice<'a>>,
+    header: &gimli::LineProgramHeader<Slice<'a>>,
+) -> Option<String> {
+    let mut path = match unit.comp_dir {
+        Some(dir) => String::from_utf8_lossy(dir.slice()).into_owned(),
+        None => String::new(),
+    };
+    if file.directory_index() != 0 {
+        if let Some(dir) = file.directory(header) {
+            let dir = dwarf.attr_string(unit, dir).ok()?;
+            path_push(&mut path, &String::from_utf8_lossy(dir.slice()));
+        }
+    }
+    let name = dwarf.attr_string(unit, file.path_name()).ok()?;
+    path_push(&mut path, &String::from_utf8_lossy(name.slice()));
+    Some(path)
+}
+
+fn path_push(path: &mut String, p: &str) {
+    if path.is_empty() || p.starts_with('/') {
+        *path = p.into();
+    } else {
+        if !path.ends_with('/') {
+            path.push('/');
+        }
+        path.push_str(p);
+    }
+}
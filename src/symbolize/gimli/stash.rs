@@ -9,14 +9,14 @@ use core::cell::UnsafeCell;
 /// A simple arena allocator for byte buffers.
 pub struct Stash {
     buffers: UnsafeCell<Vec<Vec<u8>>>,
-    mmap_aux: UnsafeCell<Option<Mmap>>,
+    mmaps: UnsafeCell<Vec<Mmap>>,
 }
 
 impl Stash {
     pub fn new() -> Stash {
         Stash {
             buffers: UnsafeCell::new(Vec::new()),
-            mmap_aux: UnsafeCell::new(None),
+            mmaps: UnsafeCell::new(Vec::new()),
         }
     }
 
@@ -33,20 +33,30 @@ impl Stash {
         &mut buffers[i]
     }
 
+    /// Returns the total size of the buffers and `Mmap`s held by this stash.
+    pub fn size(&self) -> usize {
+        // SAFETY: neither reference escapes this function, and references
+        // handed out by the methods of this type only ever point at the
+        // contents of a buffer, not at `self.buffers` itself.
+        let buffers = unsafe { &*self.buffers.get() };
+        let mmaps = unsafe { &*self.mmaps.get() };
+        buffers.iter().map(|buffer| buffer.len()).sum::<usize>()
+            + mmaps.iter().map(|map| map.len()).sum::<usize>()
+    }
+
     /// Stores a `Mmap` for the lifetime of this `Stash`, returning a pointer
     /// which is scoped to just this lifetime.
-    pub fn set_mmap_aux(&self, map: Mmap) -> &[u8] {
+    pub fn cache_mmap(&self, map: Mmap) -> &[u8] {
         // SAFETY: this is the only location for a mutable pointer to
-        // `mmap_aux`, and this structure isn't threadsafe to shared across
-        // threads either. This also is careful to store at most one `mmap_aux`
-        // since overwriting a previous one would invalidate the previous
-        // pointer. Given that though we can safely return a pointer to our
-        // interior-owned contents.
+        // `mmaps`, and this structure isn't threadsafe to shared across
+        // threads either. We also never remove elements from `self.mmaps`,
+        // and moving a `Mmap` around doesn't move the memory it maps, so a
+        // reference to the data inside any map will live as long as `self`
+        // does.
         unsafe {
-            let mmap_aux = &mut *self.mmap_aux.get();
-            assert!(mmap_aux.is_none());
-            *mmap_aux = Some(map);
-            mmap_aux.as_ref().unwrap()
+            let mmaps = &mut *self.mmaps.get();
+            mmaps.push(map);
+            mmaps.last().unwrap()
         }
     }
 }
@@ -7,7 +7,7 @@ use super::{ResolveWhat, SymbolName};
 
 pub unsafe fn resolve(what: ResolveWhat<'_>, cb: &mut dyn FnMut(&super::Symbol)) {
     let sym = match what {
-        ResolveWhat::Address(addr) => Symbol {
+        ResolveWhat::Address(addr) | ResolveWhat::ExactAddress(addr) => Symbol {
             inner: resolve_addr(addr),
             _unused: PhantomData,
         },
@@ -33,6 +33,14 @@ impl<'a> Symbol<'a> {
         Some(self.inner.addr)
     }
 
+    pub fn size(&self) -> Option<usize> {
+        None
+    }
+
+    pub fn offset(&self) -> Option<usize> {
+        None
+    }
+
     pub fn filename_raw(&self) -> Option<BytesOrWideString<'_>> {
         Some(BytesOrWideString::Bytes(&self.inner.inner.filename))
     }
@@ -51,6 +59,82 @@ impl<'a> Symbol<'a> {
             core::str::from_utf8(&self.inner.inner.filename).unwrap(),
         ))
     }
+
+    pub fn is_inlined(&self) -> bool {
+        false
+    }
+
+    pub fn call_filename_raw(&self) -> Option<BytesOrWideString<'_>> {
+        None
+    }
+
+    #[cfg(feature = "std")]
+    pub fn call_filename(&self) -> Option<&std::path::Path> {
+        None
+    }
+
+    pub fn call_lineno(&self) -> Option<u32> {
+        None
+    }
+
+    pub fn call_colno(&self) -> Option<u32> {
+        None
+    }
 }
 
 pub unsafe fn clear_symbol_cache() {}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_batch(addrs: &[*mut c_void], cb: &mut dyn FnMut(usize, &super::Symbol)) {
+    for (i, &addr) in addrs.iter().enumerate() {
+        resolve(ResolveWhat::Address(addr), &mut |sym| cb(i, sym));
+    }
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn resolve_with_modules(
+    _modules: &[crate::Module],
+    _addrs: &[ResolveWhat<'_>],
+    _cb: &mut dyn FnMut(usize, &super::Symbol),
+) {
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn find_modules(_addrs: &[*mut c_void], _cb: &mut dyn FnMut(usize, &crate::Module)) {}
+
+#[cfg(feature = "std")]
+pub unsafe fn modules() -> std::vec::Vec<crate::Module> {
+    std::vec::Vec::new()
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn set_symbol_cache_capacity(_capacity: crate::SymbolCacheCapacity) {}
+
+#[cfg(feature = "std")]
+pub unsafe fn symbol_cache_stats() -> crate::SymbolCacheStats {
+    crate::SymbolCacheStats::default()
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn set_debug_dirs(_dirs: std::vec::Vec<std::path::PathBuf>) {}
+
+#[cfg(feature = "std")]
+pub unsafe fn set_diagnostics_hook(_hook: Option<std::boxed::Box<super::DiagnosticsHook>>) {}
+
+#[cfg(feature = "std")]
+pub struct Symbolizer {
+    _private: (),
+}
+
+#[cfg(feature = "std")]
+impl Symbolizer {
+    pub unsafe fn new(_path: &std::path::Path) -> Option<Symbolizer> {
+        None
+    }
+
+    pub fn build_id(&self) -> Option<&[u8]> {
+        None
+    }
+
+    pub fn resolve(&mut self, _addr: u64, _cb: &mut dyn FnMut(&super::Symbol)) {}
+}
@@ -2,7 +2,7 @@ use core::{fmt, str};
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "std")] {
-        use std::path::Path;
+        use std::path::{Path, PathBuf};
         use std::prelude::v1::*;
     }
 }
@@ -105,8 +105,60 @@ pub fn resolve_frame<F: FnMut(&Symbol)>(frame: &Frame, cb: F) {
     unsafe { resolve_frame_unsynchronized(frame, cb) }
 }
 
+/// Resolve many addresses to symbols at once, passing each symbol along with
+/// the index in `addrs` of the address it was found for to the specified
+/// closure.
+///
+/// This gives the same symbols as calling `resolve` on each of `addrs` in
+/// turn, but is a good deal faster for large numbers of addresses, such as the
+/// samples taken by a profiler. The lock `resolve` takes is only taken once,
+/// and the addresses are grouped by the library they belong to so that the
+/// debuginfo of each library is only looked up once for all of its addresses.
+///
+/// Because of that grouping `cb` isn't called in the order of `addrs`, though
+/// the symbols of any one address are still yielded together and in the same
+/// order `resolve` would yield them in.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+///
+/// # Panics
+///
+/// See information on `resolve` for caveats on `cb` panicking.
+///
+/// # Example
+///
+/// ```
+/// extern crate backtrace;
+///
+/// fn main() {
+///     let mut ips = Vec::new();
+///     backtrace::trace(|frame| {
+///         ips.push(frame.ip());
+///         true
+///     });
+///
+///     let mut names = vec![Vec::new(); ips.len()];
+///     backtrace::resolve_batch(&ips, |i, symbol| {
+///         names[i].push(symbol.name().map(|name| name.to_string()));
+///     });
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub fn resolve_batch<F: FnMut(usize, &Symbol)>(addrs: &[*mut c_void], mut cb: F) {
+    let _guard = crate::lock::lock();
+    unsafe { imp::resolve_batch(addrs, &mut cb) }
+}
+
+#[derive(Clone, Copy)]
 pub enum ResolveWhat<'a> {
     Address(*mut c_void),
+    // An address which isn't a return address, like the ip of a deserialized
+    // frame that wasn't one.
+    #[allow(dead_code)]
+    ExactAddress(*mut c_void),
     Frame(&'a Frame),
 }
 
@@ -115,7 +167,19 @@ impl<'a> ResolveWhat<'a> {
     fn address_or_ip(&self) -> *mut c_void {
         match self {
             ResolveWhat::Address(a) => adjust_ip(*a),
-            ResolveWhat::Frame(f) => adjust_ip(f.ip()),
+            ResolveWhat::ExactAddress(a) => *a,
+            ResolveWhat::Frame(f) if f.is_return_address() => adjust_ip(f.ip()),
+            ResolveWhat::Frame(f) => f.ip(),
+        }
+    }
+
+    /// Returns the address `address_or_ip` is based on, before it's adjusted
+    /// for being a return address.
+    #[allow(dead_code)]
+    fn ip(&self) -> *mut c_void {
+        match self {
+            ResolveWhat::Address(a) | ResolveWhat::ExactAddress(a) => *a,
+            ResolveWhat::Frame(f) => f.ip(),
         }
     }
 }
@@ -131,14 +195,15 @@ impl<'a> ResolveWhat<'a> {
 //
 // Ideally we would not do this. Ideally we would require callers of the
 // `resolve` APIs here to manually do the -1 and account that they want location
-// information for the *previous* instruction, not the current. Ideally we'd
-// also expose on `Frame` if we are indeed the address of the next instruction
-// or the current.
+// information for the *previous* instruction, not the current. For frames we
+// do know better though: `Frame::is_return_address` is false for frames which
+// were interrupted by a signal rather than having made a call, and their ip
+// is resolved as-is.
 //
-// For now though this is a pretty niche concern so we just internally always
-// subtract one. Consumers should keep working and getting pretty good results,
-// so we should be good enough.
-fn adjust_ip(a: *mut c_void) -> *mut c_void {
+// For bare addresses this is a pretty niche concern so we just internally
+// always subtract one. Consumers should keep working and getting pretty good
+// results, so we should be good enough.
+pub(crate) fn adjust_ip(a: *mut c_void) -> *mut c_void {
     if a.is_null() {
         a
     } else {
@@ -178,6 +243,69 @@ where
     imp::resolve(ResolveWhat::Frame(frame), &mut cb)
 }
 
+/// Same as `resolve`, except that `addr` is resolved as-is rather than as a
+/// return address.
+#[cfg(feature = "std")]
+pub(crate) fn resolve_exact(addr: *mut c_void, mut cb: impl FnMut(&Symbol)) {
+    let _guard = crate::lock::lock();
+    unsafe { imp::resolve(ResolveWhat::ExactAddress(addr), &mut cb) }
+}
+
+/// Resolves each of `addrs` against the `modules` given, rather than against
+/// the libraries loaded into the current process, passing the index of the
+/// address along with each symbol found for it to `cb`.
+#[cfg(feature = "std")]
+pub(crate) fn resolve_with_modules(
+    modules: &[crate::Module],
+    addrs: &[ResolveWhat<'_>],
+    mut cb: impl FnMut(usize, &Symbol),
+) {
+    let _guard = crate::lock::lock();
+    unsafe { imp::resolve_with_modules(modules, addrs, &mut cb) }
+}
+
+/// Looks up which of the modules loaded into the current process each of
+/// `addrs` belongs to, passing the index of the address along with its module
+/// to `cb`. Addresses outside of any known module are skipped.
+#[cfg(feature = "std")]
+pub(crate) fn find_modules(addrs: &[*mut c_void], mut cb: impl FnMut(usize, &crate::Module)) {
+    let _guard = crate::lock::lock();
+    unsafe { imp::find_modules(addrs, &mut cb) }
+}
+
+/// Returns the modules loaded into the process `pid`, passing addresses in its
+/// address space along with buffers to fill with the memory there to `read`,
+/// which returns whether it could read all of it.
+#[cfg(all(
+    feature = "ptrace",
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+    not(miri),
+))]
+pub(crate) fn remote_modules(
+    pid: u32,
+    mut read: impl FnMut(usize, &mut [u8]) -> bool,
+) -> Vec<crate::Module> {
+    imp::remote_modules(pid, &mut read)
+}
+
+/// Returns the modules loaded into another process at `mappings`, the start
+/// and end of each mapping of the start of a file along with the path of the
+/// file, reading the memory of the process through `read` as for
+/// `remote_modules`.
+#[cfg(all(
+    feature = "coredump",
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+    not(miri),
+))]
+pub(crate) fn mapped_modules(
+    mappings: &[(usize, usize, &std::ffi::OsStr)],
+    mut read: impl FnMut(usize, &mut [u8]) -> bool,
+) -> Vec<crate::Module> {
+    imp::mapped_modules(mappings, &mut read)
+}
+
 /// A trait representing the resolution of a symbol in a file.
 ///
 /// This trait is yielded as a trait object to the closure given to the
@@ -209,10 +337,34 @@ impl Symbol {
     }
 
     /// Returns the starting address of this function.
+    ///
+    /// For functions which were inlined (see `is_inlined`) this is the
+    /// starting address of the function they were inlined into.
     pub fn addr(&self) -> Option<*mut c_void> {
         self.inner.addr().map(|p| p as *mut _)
     }
 
+    /// Returns the size in bytes of this function's code, starting at `addr`.
+    ///
+    /// This is known from the symbol table for ELF and from DWARF debuginfo
+    /// with gimli, and from dbghelp on Windows. For inlined functions it's
+    /// the size of the function they were inlined into.
+    pub fn size(&self) -> Option<usize> {
+        self.inner.size()
+    }
+
+    /// Returns the offset of the instruction pointer from `addr`, the start of
+    /// the function, as printed by `PrintFmt::FullWithOffsets`.
+    ///
+    /// This is the offset of the address passed to `resolve`, or of the `ip`
+    /// of the frame passed to `resolve_frame`, even where the symbol is that
+    /// of the address before it as it's a return address (see
+    /// `Frame::is_return_address`). Only gimli currently provides a value
+    /// here, if it found where the function starts.
+    pub fn offset(&self) -> Option<usize> {
+        self.inner.offset()
+    }
+
     /// Returns the raw filename as a slice. This is mainly useful for `no_std`
     /// environments.
     pub fn filename_raw(&self) -> Option<BytesOrWideString<'_>> {
@@ -251,6 +403,53 @@ impl Symbol {
     pub fn filename(&self) -> Option<&Path> {
         self.inner.filename()
     }
+
+    /// Returns whether this function was inlined into its caller.
+    ///
+    /// When functions were inlined several symbols are resolved for one
+    /// address, the innermost inlined function first. Each of them but the
+    /// last is inlined; the last is the function the code was actually
+    /// compiled into.
+    ///
+    /// Only gimli currently knows about inlined functions, elsewhere this
+    /// always returns `false`.
+    pub fn is_inlined(&self) -> bool {
+        self.inner.is_inlined()
+    }
+
+    /// Returns the raw filename of the call site this function was inlined
+    /// at, if it was inlined. This is mainly useful for `no_std`
+    /// environments.
+    pub fn call_filename_raw(&self) -> Option<BytesOrWideString<'_>> {
+        self.inner.call_filename_raw()
+    }
+
+    /// Returns the column number of the call site this function was inlined
+    /// at, if it was inlined.
+    pub fn call_colno(&self) -> Option<u32> {
+        self.inner.call_colno()
+    }
+
+    /// Returns the line number of the call site this function was inlined at,
+    /// if it was inlined.
+    ///
+    /// This is the same as the `lineno` of the next symbol resolved for the
+    /// same address, which is the caller.
+    pub fn call_lineno(&self) -> Option<u32> {
+        self.inner.call_lineno()
+    }
+
+    /// Returns the file name of the call site this function was inlined at,
+    /// if it was inlined.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `std` feature of the `backtrace` crate to be
+    /// enabled, and the `std` feature is enabled by default.
+    #[cfg(feature = "std")]
+    pub fn call_filename(&self) -> Option<&Path> {
+        self.inner.call_filename()
+    }
 }
 
 impl fmt::Debug for Symbol {
@@ -273,6 +472,9 @@ impl fmt::Debug for Symbol {
         if let Some(lineno) = self.lineno() {
             d.field("lineno", &lineno);
         }
+        if self.is_inlined() {
+            d.field("inlined", &true);
+        }
         d.finish()
     }
 }
@@ -463,6 +665,315 @@ pub fn clear_symbol_cache() {
     }
 }
 
+/// Returns a description of every image (the main executable and each shared
+/// library) currently loaded into this process.
+///
+/// Each `Module` lists the path of the image, the bias it was loaded with, the
+/// segments which were mapped into memory, and its GNU build ID, Mach-O UUID
+/// or PDB GUID and age where one could be found. This is typically recorded next to an unresolved
+/// `Backtrace` so that it can be symbolicated later on with
+/// `Backtrace::resolve_with_modules`.
+///
+/// # Caveats
+///
+/// The list is read anew from the operating system on each call. Only the
+/// `gimli-symbolize` and `dbghelp` implementations know how to enumerate
+/// images, every other implementation returns an empty list.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+pub fn modules() -> Vec<crate::Module> {
+    let _guard = crate::lock::lock();
+    unsafe { imp::modules() }
+}
+
+/// Sets the directories to search for separate debuginfo files.
+///
+/// When the debuginfo of an image has been split out into a separate file it
+/// is located through the image's build ID, looking for
+/// `<dir>/.build-id/xx/yyyy.debug`, or through its `.gnu_debuglink` section,
+/// looking for `<dir>/<image's directory>/<debuglink name>`. The `dirs` given
+/// here are tried in order, followed by the directories listed in the
+/// `BACKTRACE_DEBUG_DIRS` environment variable (separated like `PATH` is), and
+/// finally `/usr/lib/debug`. Relative paths are ignored.
+///
+/// Each call replaces the directories given by the previous one.
+///
+/// # Caveats
+///
+/// Debuginfo which has already been loaded is cached, so this generally only
+/// affects images which haven't been symbolicated yet. Call
+/// `clear_symbol_cache` to have everything looked up again. Only the
+/// `gimli-symbolize` implementation supports separate debuginfo, on ELF
+/// platforms, so this has no effect elsewhere.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+pub fn set_debug_dirs<I>(dirs: I)
+where
+    I: IntoIterator,
+    I::Item: Into<PathBuf>,
+{
+    let dirs = dirs.into_iter().map(Into::into).collect();
+    let _guard = crate::lock::lock();
+    unsafe { imp::set_debug_dirs(dirs) }
+}
+
+/// An event of interest which happened while locating or loading debuginfo.
+///
+/// These are reported to the hook installed with `set_diagnostics_hook`, and
+/// explain why debuginfo which might have been expected to be used wasn't.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum Diagnostic<'a> {
+    /// A separate debug file found through a `.gnu_debuglink` section was
+    /// rejected because its CRC didn't match the one recorded in the section.
+    ///
+    /// This typically means that the debug file is stale, i.e. it was split
+    /// off of an earlier build of the binary. The search for debuginfo then
+    /// continues with the next candidate file, or with the sections of the
+    /// binary itself.
+    DebuglinkCrcMismatch {
+        /// The path of the rejected debug file.
+        path: &'a Path,
+        /// The CRC recorded in the `.gnu_debuglink` section.
+        expected: u32,
+        /// The CRC of the debug file's contents.
+        actual: u32,
+    },
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+#[cfg(feature = "std")]
+type DiagnosticsHook = dyn Fn(&Diagnostic<'_>) + Send + Sync;
+
+/// Installs a hook which is called with each `Diagnostic` reported while
+/// locating or loading debuginfo, replacing any previous hook.
+///
+/// Passing `None` removes the current hook, and diagnostics are ignored when
+/// no hook is installed.
+///
+/// # Caveats
+///
+/// The hook is called while this crate's global lock is held, so it's never
+/// called concurrently. It's only called once the crate's caches are no
+/// longer in use though, so it may itself resolve symbols, capture backtraces
+/// or call `set_diagnostics_hook`. Only the `gimli-symbolize` implementation
+/// reports any diagnostics.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+pub fn set_diagnostics_hook(hook: Option<Box<DiagnosticsHook>>) {
+    let _guard = crate::lock::lock();
+    unsafe { imp::set_diagnostics_hook(hook) }
+}
+
+/// Symbolizes addresses within a single object file on disk.
+///
+/// Unlike `resolve`, which looks up addresses in the images loaded into the
+/// current process, a `Symbolizer` opens an arbitrary object file and resolves
+/// addresses as stated in that file (i.e. not relocated by wherever it may be
+/// loaded). This makes it possible to build `addr2line`-style tools on top of
+/// this crate.
+///
+/// Debuginfo is located the same way as it is for the current process: split
+/// debuginfo found through the file's build ID or `.gnu_debuglink` section
+/// (see `set_debug_dirs`) is used, as is supplementary debuginfo named by
+/// `.gnu_debugaltlink`, and compressed sections are decompressed.
+///
+/// # Caveats
+///
+/// Only the `gimli-symbolize` implementation supports this, and only for
+/// object files in the format native to the current platform. Elsewhere
+/// `Symbolizer::new` always returns `None`.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+pub struct Symbolizer {
+    inner: imp::Symbolizer,
+}
+
+#[cfg(feature = "std")]
+impl Symbolizer {
+    /// Opens the object file at `path` for symbolization.
+    ///
+    /// Returns `None` if the file can't be read or isn't an object file that
+    /// this crate knows how to parse.
+    pub fn new<P: AsRef<Path>>(path: P) -> Option<Symbolizer> {
+        let _guard = crate::lock::lock();
+        let inner = unsafe { imp::Symbolizer::new(path.as_ref())? };
+        Some(Symbolizer { inner })
+    }
+
+    /// Returns the GNU build ID (or Mach-O UUID) of the file that debuginfo is
+    /// read from, if it has one.
+    pub fn build_id(&self) -> Option<&[u8]> {
+        self.inner.build_id()
+    }
+
+    /// Resolves `addr`, an address as stated in the object file, passing each
+    /// symbol found to the specified closure.
+    ///
+    /// Like `resolve`, this may yield multiple symbols for one address when
+    /// functions were inlined: the innermost inlined frame is yielded first
+    /// and the function it was ultimately inlined into is yielded last.
+    pub fn resolve<F: FnMut(&Symbol)>(&mut self, addr: u64, mut cb: F) {
+        self.inner.resolve(addr, &mut cb)
+    }
+}
+
+/// The capacity of the cache of parsed debuginfo, see
+/// `set_symbol_cache_capacity`.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SymbolCacheCapacity {
+    /// Keeps the debuginfo of at most this many images (executables or shared
+    /// libraries) at once.
+    Entries(usize),
+    /// Keeps as much debuginfo as fits in approximately this many bytes.
+    ///
+    /// The size of an image's debuginfo is approximated by the size of the
+    /// files mapped into memory for it plus that of any sections which had to
+    /// be decompressed, so it doesn't account for the data structures built
+    /// while looking up addresses.
+    Bytes(usize),
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+/// Statistics about the cache of parsed debuginfo, as returned by
+/// `symbol_cache_stats`.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SymbolCacheStats {
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    entries: usize,
+    bytes: usize,
+}
+
+#[cfg(feature = "std")]
+impl SymbolCacheStats {
+    /// Returns how many times the debuginfo of an image was already cached
+    /// when it was needed.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns how many times the debuginfo of an image had to be loaded
+    /// because it wasn't cached, whether or not loading it succeeded.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Returns how many times debuginfo was dropped from the cache to make
+    /// room for other debuginfo, or because the capacity was lowered.
+    ///
+    /// Clearing the cache with `clear_symbol_cache` doesn't count as evicting.
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    /// Returns the number of images whose debuginfo is currently cached.
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+
+    /// Returns the approximate number of bytes currently held by the cache,
+    /// measured the same way as for `SymbolCacheCapacity::Bytes`.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+/// Sets the capacity of the global cache of parsed debuginfo.
+///
+/// Parsing the debuginfo of an image is expensive, so it's cached between
+/// calls to `resolve` and friends, evicting the least recently used entries
+/// once the cache is full. By default the debuginfo of at most 4 images is
+/// kept, which works well for backtraces that only cross a handful of shared
+/// libraries; programs whose backtraces commonly cross more of them can avoid
+/// repeatedly parsing the same debuginfo by raising the capacity.
+///
+/// Lowering the capacity immediately evicts entries as needed. The debuginfo
+/// of the image currently being looked up is always kept, even if it alone
+/// exceeds the capacity.
+///
+/// The debuginfo of modules given to `Backtrace::resolve_with_modules` is
+/// kept in a cache of its own, which has the same capacity.
+///
+/// # Caveats
+///
+/// Only the `gimli-symbolize` implementation maintains such a cache, so this
+/// function has no effect elsewhere.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+pub fn set_symbol_cache_capacity(capacity: SymbolCacheCapacity) {
+    let _guard = crate::lock::lock();
+    unsafe { imp::set_symbol_cache_capacity(capacity) }
+}
+
+/// Returns statistics about the global cache of parsed debuginfo.
+///
+/// The counters accumulate from the start of the process, and aren't reset by
+/// `clear_symbol_cache`.
+///
+/// # Caveats
+///
+/// Only the `gimli-symbolize` implementation maintains such a cache, so all
+/// statistics are zero elsewhere.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[cfg(feature = "std")]
+pub fn symbol_cache_stats() -> SymbolCacheStats {
+    let _guard = crate::lock::lock();
+    unsafe { imp::symbol_cache_stats() }
+}
+
+#[cfg(all(
+    windows,
+    not(target_vendor = "uwp"),
+    not(miri),
+    any(feature = "std", not(target_env = "msvc")),
+))]
+mod toolhelp;
+
 cfg_if::cfg_if! {
     if #[cfg(miri)] {
         mod miri;
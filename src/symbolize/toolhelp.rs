@@ -0,0 +1,97 @@
+//! Enumeration of the images loaded into the current process on Windows,
+//! shared by the `gimli` and `dbghelp` implementations.
+//!
+//! For loading native libraries on Windows, see some discussion on
+//! rust-lang/rust#71060 for the various strategies here.
+
+use super::super::windows::*;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::mem;
+use core::mem::MaybeUninit;
+use core::slice;
+use object::pe::{ImageDebugDirectory, ImageDosHeader};
+use object::read::pe::{ImageNtHeaders, ImageOptionalHeader};
+use object::read::ReadRef;
+use object::LittleEndian as LE;
+
+#[cfg(target_pointer_width = "32")]
+type Pe = object::pe::ImageNtHeaders32;
+#[cfg(target_pointer_width = "64")]
+type Pe = object::pe::ImageNtHeaders64;
+
+/// Calls `f` with each of the images currently loaded into this process, as
+/// listed by a toolhelp snapshot.
+pub(super) unsafe fn for_each_loaded_image(mut f: impl FnMut(&MODULEENTRY32W)) {
+    let snap = CreateToolhelp32Snapshot(TH32CS_SNAPMODULE, 0);
+    if snap == INVALID_HANDLE_VALUE {
+        return;
+    }
+
+    let mut me = MaybeUninit::<MODULEENTRY32W>::zeroed().assume_init();
+    me.dwSize = mem::size_of_val(&me) as DWORD;
+    if Module32FirstW(snap, &mut me) == TRUE {
+        loop {
+            f(&me);
+
+            if Module32NextW(snap, &mut me) != TRUE {
+                break;
+            }
+        }
+    }
+
+    CloseHandle(snap);
+}
+
+/// Returns the path of the file the image `me` was loaded from.
+pub(super) fn image_path(me: &MODULEENTRY32W) -> &[u16] {
+    let pos = me
+        .szExePath
+        .iter()
+        .position(|i| *i == 0)
+        .unwrap_or(me.szExePath.len());
+    &me.szExePath[..pos]
+}
+
+/// Returns the "image base" stated in the headers of the PE file `data`, the
+/// address it's linked to be loaded at.
+pub(super) fn image_base(data: &[u8]) -> Option<usize> {
+    let dos_header = ImageDosHeader::parse(data).ok()?;
+    let mut offset = dos_header.nt_headers_offset().into();
+    let (nt_headers, _) = Pe::parse(data, &mut offset).ok()?;
+    usize::try_from(nt_headers.optional_header().image_base()).ok()
+}
+
+/// Returns the GUID and age of the PDB the image `me` was linked with, which
+/// together identify its build, read from the image's CodeView record.
+pub(super) unsafe fn codeview_id(me: &MODULEENTRY32W) -> Option<Vec<u8>> {
+    // The image is mapped as a whole, so the relative virtual addresses in
+    // its headers are offsets from where it was loaded.
+    let image = slice::from_raw_parts(me.modBaseAddr as *const u8, me.modBaseSize as usize);
+    let dos_header = ImageDosHeader::parse(image).ok()?;
+    let mut offset = dos_header.nt_headers_offset().into();
+    let (_, data_directories) = Pe::parse(image, &mut offset).ok()?;
+    let debug = data_directories.get(object::pe::IMAGE_DIRECTORY_ENTRY_DEBUG)?;
+    let entries = image
+        .read_slice_at::<ImageDebugDirectory>(
+            debug.virtual_address.get(LE).into(),
+            debug.size.get(LE) as usize / mem::size_of::<ImageDebugDirectory>(),
+        )
+        .ok()?;
+    for entry in entries {
+        if entry.typ.get(LE) != object::pe::IMAGE_DEBUG_TYPE_CODEVIEW {
+            continue;
+        }
+        let info = image
+            .read_bytes_at(
+                entry.address_of_raw_data.get(LE).into(),
+                entry.size_of_data.get(LE).into(),
+            )
+            .ok()?;
+        // An "RSDS" signature, then the 16 byte GUID and 4 byte age.
+        if info.len() >= 24 && info.starts_with(b"RSDS") {
+            return Some(info[4..24].to_vec());
+        }
+    }
+    None
+}
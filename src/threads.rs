@@ -0,0 +1,313 @@
+//! Capturing the backtraces of other threads of the current process.
+//!
+//! A thread can only be unwound from inside of itself, since walking a stack
+//! that's still changing isn't going to produce anything useful. So each
+//! thread is sent a signal, and the signal handler unwinds the context the
+//! signal interrupted into a buffer shared with the requesting thread. Only one
+//! thread is asked at a time, which keeps the handler free of allocations and
+//! locks.
+
+use crate::backtrace::{trace_from_context_unsynchronized, FrameImp, PRESERVED_REGISTERS};
+use crate::{Backtrace, BacktraceFrame};
+use std::cell::UnsafeCell;
+use std::ffi::c_void;
+use std::fmt;
+use std::fs;
+use std::prelude::v1::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Once;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The backtrace of one thread of a process, as returned by `thread_backtraces`
+/// and `thread_backtrace` for the current process, or by
+/// `RemoteProcess::backtraces` and `CoreDump::backtraces` for another one.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default.
+#[derive(Clone)]
+pub struct ThreadBacktrace {
+    id: u32,
+    name: Option<String>,
+    backtrace: Backtrace,
+}
+
+impl ThreadBacktrace {
+    /// Creates the backtrace of the thread `id`, named `comm` according to its
+    /// `comm` file in `/proc`.
+    pub(crate) fn new(id: u32, comm: &str, backtrace: Backtrace) -> ThreadBacktrace {
+        let name = comm.trim_end_matches('\n');
+        ThreadBacktrace {
+            id,
+            name: if name.is_empty() {
+                None
+            } else {
+                Some(name.to_string())
+            },
+            backtrace,
+        }
+    }
+
+    /// Returns the operating system's id of the thread, as returned by
+    /// `gettid`.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the name of the thread, if it has one.
+    ///
+    /// This is the name the kernel knows the thread by, which for threads
+    /// spawned by the standard library is the thread's name truncated to 15
+    /// bytes.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the backtrace of the thread.
+    ///
+    /// The first frame is the one the thread was executing when it was asked
+    /// for its backtrace, or the frame of `thread_backtraces` for the thread
+    /// that called it.
+    pub fn backtrace(&self) -> &Backtrace {
+        &self.backtrace
+    }
+}
+
+impl fmt::Debug for ThreadBacktrace {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            Some(ref name) => writeln!(fmt, "thread '{}' ({}):", name, self.id)?,
+            None => writeln!(fmt, "thread {}:", self.id)?,
+        }
+        fmt::Debug::fmt(&self.backtrace, fmt)
+    }
+}
+
+/// Captures the backtraces of all threads of the current process.
+///
+/// Every thread other than the calling one is sent a signal (the last
+/// real-time signal, `SIGRTMAX`) and unwinds itself from the context the
+/// signal interrupted. A signal handler for it is installed the first time this
+/// is called and stays installed afterwards, so this shouldn't be used by
+/// programs that use `SIGRTMAX` themselves. Threads which don't handle the
+/// signal in time, for example because they block it, are left out.
+///
+/// The backtraces are resolved before they're returned.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default. It's only available
+/// on Linux on x86_64 and aarch64.
+///
+/// # Example
+///
+/// ```
+/// for thread in backtrace::thread_backtraces() {
+///     println!("{:?}", thread);
+/// }
+/// ```
+pub fn thread_backtraces() -> Vec<ThreadBacktrace> {
+    let ids = match fs::read_dir("/proc/self/task") {
+        Ok(dir) => dir
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+            .collect::<Vec<u32>>(),
+        Err(_) => return Vec::new(),
+    };
+    ids.into_iter().filter_map(thread_backtrace).collect()
+}
+
+/// Captures the backtrace of the thread of the current process with the
+/// operating system id `id`, as returned by `gettid`.
+///
+/// Returns `None` if there's no such thread or it didn't respond. See
+/// `thread_backtraces` for how the backtrace is captured.
+///
+/// # Required features
+///
+/// This function requires the `std` feature of the `backtrace` crate to be
+/// enabled, and the `std` feature is enabled by default. It's only available
+/// on Linux on x86_64 and aarch64.
+pub fn thread_backtrace(id: u32) -> Option<ThreadBacktrace> {
+    let comm = fs::read_to_string(format!("/proc/self/task/{}/comm", id)).ok()?;
+    let mut backtrace = if id == current_thread_id() {
+        Backtrace::new_unresolved()
+    } else {
+        capture(id)?
+    };
+    backtrace.resolve();
+    Some(ThreadBacktrace::new(id, &comm, backtrace))
+}
+
+const MAX_FRAMES: usize = 256;
+
+// How long a thread gets to start handling the signal before it's given up on.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+// How long a backtrace may take altogether, from waiting for an earlier request
+// to finish to the thread being done unwinding itself, before it's given up on.
+// A thread may never finish, for example if it's stopped by a debugger while
+// it's unwinding.
+const DEADLINE: Duration = Duration::from_secs(5);
+
+// Values of `STATE` other than the id of the thread a backtrace is requested
+// from.
+const IDLE: usize = 0;
+const CAPTURING: usize = usize::max_value();
+const CAPTURED: usize = usize::max_value() - 1;
+// The requesting thread gave up while the thread was unwinding, which then
+// moves `STATE` back to `IDLE` itself once it's done.
+const ABANDONED: usize = usize::max_value() - 2;
+
+static STATE: AtomicUsize = AtomicUsize::new(IDLE);
+static LEN: AtomicUsize = AtomicUsize::new(0);
+static FRAMES: Frames = Frames(UnsafeCell::new([[0; 4]; MAX_FRAMES]));
+
+// The ip, sp and symbol address of each frame captured by the signal handler,
+// and whether the ip is a return address.
+struct Frames(UnsafeCell<[[usize; 4]; MAX_FRAMES]>);
+
+// Only the thread which moved `STATE` from its id to `CAPTURING` writes to the
+// frames, and the requesting thread only reads them once `STATE` is
+// `CAPTURED`.
+unsafe impl Sync for Frames {}
+
+// Only one backtrace is requested at a time, which is made sure of through
+// `STATE`: it's only moved from `IDLE` to the id of a thread by one requesting
+// thread at a time, and only goes back to `IDLE` once nothing uses the frames
+// anymore. The crate's lock isn't held, so a thread which never finishes
+// unwinding doesn't hold up anything but other requests for backtraces.
+fn capture(id: u32) -> Option<Backtrace> {
+    install_handler();
+
+    let start = Instant::now();
+    while STATE
+        .compare_exchange(IDLE, id as usize, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        if start.elapsed() > DEADLINE {
+            return None;
+        }
+        thread::yield_now();
+    }
+
+    let sent = unsafe {
+        libc::syscall(
+            libc::SYS_tgkill,
+            libc::getpid(),
+            id as libc::pid_t,
+            signal(),
+        )
+    };
+    if sent != 0 {
+        STATE.store(IDLE, Ordering::SeqCst);
+        return None;
+    }
+
+    let sent_at = Instant::now();
+    loop {
+        match STATE.load(Ordering::SeqCst) {
+            CAPTURED => break,
+            // Once the thread started unwinding it's writing to the frames, so
+            // if it's given up on they're left to it until it's done.
+            CAPTURING
+                if start.elapsed() > DEADLINE
+                    && STATE
+                        .compare_exchange(CAPTURING, ABANDONED, Ordering::SeqCst, Ordering::SeqCst)
+                        .is_ok() =>
+            {
+                return None;
+            }
+            CAPTURING => {}
+            // If the thread started handling the signal in the meantime it has
+            // to be waited for after all.
+            _ if sent_at.elapsed() > TIMEOUT
+                && STATE
+                    .compare_exchange(id as usize, IDLE, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok() =>
+            {
+                return None;
+            }
+            _ => {}
+        }
+        thread::yield_now();
+    }
+
+    let len = LEN.load(Ordering::SeqCst);
+    let captured = unsafe { &*FRAMES.0.get() };
+    let frames: Vec<BacktraceFrame> = captured[..len]
+        .iter()
+        .map(|&[ip, sp, symbol_address, is_return_address]| {
+            BacktraceFrame::captured(crate::Frame {
+                inner: FrameImp::from_addresses(
+                    ip as *mut c_void,
+                    sp as *mut c_void,
+                    symbol_address as *mut c_void,
+                    is_return_address != 0,
+                    None,
+                    [None; PRESERVED_REGISTERS.len()],
+                ),
+            })
+        })
+        .collect();
+    STATE.store(IDLE, Ordering::SeqCst);
+    Some(Backtrace::from(frames))
+}
+
+fn signal() -> libc::c_int {
+    libc::SIGRTMAX()
+}
+
+fn current_thread_id() -> u32 {
+    unsafe { libc::syscall(libc::SYS_gettid) as u32 }
+}
+
+fn install_handler() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handler as *const () as usize;
+        // Not `SA_ONSTACK`: unwinding needs more stack than the alternate
+        // signal stacks the standard library sets up.
+        action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(signal(), &action, std::ptr::null_mut());
+    });
+}
+
+extern "C" fn handler(_: libc::c_int, _: *mut libc::siginfo_t, context: *mut libc::c_void) {
+    // Ignore signals which weren't sent for a backtrace of this thread, or
+    // arrive after the requesting thread gave up on this one.
+    let id = current_thread_id() as usize;
+    if STATE
+        .compare_exchange(id, CAPTURING, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        return;
+    }
+
+    let frames = unsafe { &mut *FRAMES.0.get() };
+    let mut len = 0;
+    unsafe {
+        trace_from_context_unsynchronized(context, |frame| {
+            frames[len] = [
+                frame.ip() as usize,
+                frame.sp() as usize,
+                frame.symbol_address() as usize,
+                frame.is_return_address() as usize,
+            ];
+            len += 1;
+            len < MAX_FRAMES
+        });
+    }
+    LEN.store(len, Ordering::SeqCst);
+    if STATE
+        .compare_exchange(CAPTURING, CAPTURED, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        // The requesting thread gave up on this one in the meantime.
+        STATE.store(IDLE, Ordering::SeqCst);
+    }
+}
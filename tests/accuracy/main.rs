@@ -23,8 +23,6 @@ fn doit() {
     // Skip Miri, since it doesn't support dynamic libraries.
     && !cfg!(miri)
     {
-        // TODO(#238) this shouldn't have to happen first in this function, but
-        // currently it does.
         let mut dir = std::env::current_exe().unwrap();
         dir.pop();
         if cfg!(windows) {
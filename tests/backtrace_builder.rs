@@ -0,0 +1,55 @@
+// Tests of `BacktraceBuilder` which hold whether or not the frames of
+// `BacktraceBuilder::capture` can be told apart and skipped, so only compare
+// backtraces captured the same way.
+
+use backtrace::Backtrace;
+
+#[test]
+fn skip_and_max_frames() {
+    let full = Backtrace::builder().resolve(false).capture();
+    let part = Backtrace::builder()
+        .skip(1)
+        .max_frames(2)
+        .resolve(false)
+        .capture();
+
+    assert!(full.frames().len() > 3);
+    assert_eq!(part.frames().len(), 2);
+    assert!(part.frames()[0].symbols().is_empty());
+    for (full, part) in full.frames()[1..].iter().zip(part.frames()) {
+        assert_eq!(full.ip(), part.ip());
+    }
+}
+
+#[test]
+fn max_frames_on_a_deep_stack() {
+    #[inline(never)]
+    fn recurse(depth: usize) -> Backtrace {
+        if depth == 0 {
+            Backtrace::builder().max_frames(10).resolve(false).capture()
+        } else {
+            let bt = recurse(depth - 1);
+            assert_eq!(bt.frames().len(), 10);
+            bt
+        }
+    }
+    assert_eq!(recurse(1000).frames().len(), 10);
+}
+
+#[test]
+fn stop_at() {
+    let full = Backtrace::builder().resolve(false).capture();
+    let outermost = full.frames().last().unwrap().ip() as usize;
+
+    let mut calls = 0;
+    let b = Backtrace::builder()
+        .skip(1)
+        .stop_at(|frame| {
+            calls += 1;
+            frame.ip() as usize == outermost
+        })
+        .resolve(false)
+        .capture();
+    assert_eq!(b.frames().len(), full.frames().len() - 2);
+    assert_eq!(calls, full.frames().len() - 1);
+}
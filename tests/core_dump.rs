@@ -0,0 +1,154 @@
+// Tests for capturing backtraces from core dumps. The process dumped is this
+// test binary itself, re-run to only execute `child`, which aborts.
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+))]
+mod linux {
+    use backtrace::CoreDump;
+    use std::env;
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+    use std::process::{self, Command};
+
+    const CHILD: &str = "BACKTRACE_CORE_DUMP_CHILD";
+
+    #[inline(never)]
+    fn crash_in_child() {
+        process::abort();
+    }
+
+    #[test]
+    fn child() {
+        if env::var_os(CHILD).is_some() {
+            crash_in_child();
+        }
+    }
+
+    // Has the child dump core into an empty directory, returning the directory
+    // and the core file, or `None` if the system doesn't write core files into
+    // the working directory of the crashed process (or at all).
+    fn dump_core() -> Option<(PathBuf, PathBuf)> {
+        let pattern = fs::read_to_string("/proc/sys/kernel/core_pattern").ok()?;
+        if pattern.starts_with('|') || pattern.contains('/') {
+            return None;
+        }
+        let dir = env::temp_dir().join(format!("backtrace-core-dump-{}", process::id()));
+        drop(fs::remove_dir_all(&dir));
+        fs::create_dir_all(&dir).unwrap();
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg("ulimit -c unlimited && exec \"$0\" --exact linux::child --nocapture")
+            .arg(env::current_exe().unwrap())
+            .env(CHILD, "1")
+            .current_dir(&dir)
+            .status()
+            .unwrap();
+        assert!(!status.success());
+        let core = fs::read_dir(&dir).unwrap().next();
+        match core {
+            Some(core) => Some((dir, core.unwrap().path())),
+            None => {
+                drop(fs::remove_dir_all(&dir));
+                None
+            }
+        }
+    }
+
+    fn has_frame(thread: &backtrace::ThreadBacktrace, suffix: &str) -> bool {
+        thread
+            .backtrace()
+            .frames()
+            .iter()
+            .flat_map(|f| f.symbols())
+            .filter_map(|s| s.name())
+            .any(|n| format!("{:#}", n).ends_with(suffix))
+    }
+
+    #[test]
+    fn backtraces_from_core() {
+        let (dir, core) = match dump_core() {
+            Some(dumped) => dumped,
+            None => {
+                println!("core files aren't dumped into the working directory, skipping");
+                return;
+            }
+        };
+        let threads = {
+            let core = CoreDump::open(&core).unwrap();
+            let exe = env::current_exe().unwrap();
+            assert!(core.modules().iter().any(|m| m.path() == exe));
+            assert!(!core.threads().is_empty());
+            core.backtraces()
+        };
+        fs::remove_dir_all(&dir).unwrap();
+
+        for thread in &threads {
+            println!("{:?}", thread);
+        }
+        // The thread which aborted is the one that caused the dump, and has to
+        // be unwound from within libc back into the test.
+        let crashed = &threads[0];
+        assert!(has_frame(crashed, "linux::crash_in_child"));
+        assert!(has_frame(crashed, "linux::child"));
+    }
+
+    // Writes a core file for the current architecture made up of just the ELF
+    // header and one `PT_NOTE` program header, with the offsets given.
+    fn write_core(name: &str, phoff: u64, note_offset: u64, note_len: u64) -> PathBuf {
+        let machine: u16 = if cfg!(target_arch = "x86_64") {
+            62
+        } else {
+            183
+        };
+        let mut data = vec![0x7f, b'E', b'L', b'F', 2, 1, 1];
+        data.resize(16, 0);
+        data.extend(&4u16.to_le_bytes()); // e_type: ET_CORE
+        data.extend(&machine.to_le_bytes());
+        data.extend(&1u32.to_le_bytes());
+        data.extend(&0u64.to_le_bytes());
+        data.extend(&phoff.to_le_bytes());
+        data.extend(&0u64.to_le_bytes());
+        data.extend(&0u32.to_le_bytes());
+        data.extend(&64u16.to_le_bytes());
+        data.extend(&56u16.to_le_bytes()); // e_phentsize
+        data.extend(&1u16.to_le_bytes()); // e_phnum
+        data.extend(&[0; 6]);
+        data.extend(&4u32.to_le_bytes()); // p_type: PT_NOTE
+        data.extend(&0u32.to_le_bytes());
+        data.extend(&note_offset.to_le_bytes());
+        data.extend(&[0; 16]);
+        data.extend(&note_len.to_le_bytes());
+        data.extend(&note_len.to_le_bytes());
+        data.extend(&4u64.to_le_bytes());
+
+        let path = env::temp_dir().join(format!("backtrace-{}-{}", name, process::id()));
+        fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn corrupt_core_files() {
+        // Program headers, or notes, said to be far beyond the end of the
+        // file mustn't be read, let alone allocated for.
+        let cores = [
+            write_core("core-phoff", 1 << 40, 0, 0),
+            write_core("core-notes", 64, 1 << 40, 16),
+            write_core("core-notes-len", 64, 120, 1 << 40),
+            write_core("core-truncated", 64, 120, 16),
+        ];
+        for core in &cores {
+            let err = CoreDump::open(core).err().unwrap();
+            fs::remove_file(core).unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn not_a_core_file() {
+        let err = CoreDump::open(env::current_exe().unwrap()).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
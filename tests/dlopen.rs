@@ -0,0 +1,107 @@
+// Tests for symbolicating code in libraries loaded (and unloaded) at runtime,
+// after the cache of loaded libraries has been populated.
+
+use backtrace::Backtrace;
+use std::path::PathBuf;
+
+type Pos = (&'static str, u32);
+
+fn dylib_dep_path() -> PathBuf {
+    let mut dir = std::env::current_exe().unwrap();
+    dir.pop();
+    if cfg!(windows) {
+        dir.push("dylib_dep.dll");
+    } else if cfg!(target_os = "macos") {
+        dir.push("libdylib_dep.dylib");
+    } else {
+        dir.push("libdylib_dep.so");
+    }
+    dir
+}
+
+// Calls the `foo` function of `lib`, returning a backtrace captured from
+// within it.
+fn backtrace_through_foo(lib: &libloading::Library) -> Backtrace {
+    thread_local!(static CAPTURED: std::cell::RefCell<Option<Backtrace>> = Default::default());
+    unsafe {
+        let api = lib.get::<extern "C" fn(Pos, fn(Pos, Pos))>(b"foo").unwrap();
+        api((file!(), line!()), |_, _| {
+            CAPTURED.with(|c| *c.borrow_mut() = Some(Backtrace::new()));
+        });
+    }
+    CAPTURED.with(|c| c.borrow_mut().take().unwrap())
+}
+
+fn contains_foo(bt: &Backtrace) -> bool {
+    bt.frames().iter().flat_map(|f| f.symbols()).any(|s| {
+        let name = s.name().map(|n| n.to_string());
+        let file = s.filename().map(|f| f.to_owned());
+        name.as_ref().map(|n| n == "foo").unwrap_or(false)
+            && file
+                .map(|f| f.ends_with("dylib-dep/src/lib.rs"))
+                .unwrap_or(false)
+    })
+}
+
+#[test]
+fn library_loaded_after_first_resolve() {
+    // Skip musl which is by default statically linked and doesn't support
+    // dynamic libraries, and Miri which doesn't support them either.
+    if cfg!(target_env = "musl") || cfg!(miri) {
+        return;
+    }
+
+    // Make sure the list of libraries is loaded before the library is.
+    let bt = Backtrace::new();
+    assert!(!contains_foo(&bt));
+
+    let lib = unsafe { libloading::Library::new(dylib_dep_path()).unwrap() };
+    let bt = backtrace_through_foo(&lib);
+    println!("{:?}", bt);
+    assert!(contains_foo(&bt));
+}
+
+#[test]
+fn library_replaced_after_unload() {
+    // Skip musl which is by default statically linked and doesn't support
+    // dynamic libraries, and Miri which doesn't support them either.
+    if cfg!(target_env = "musl") || cfg!(miri) {
+        return;
+    }
+
+    // Another library to load once the first one is unloaded, which is likely
+    // to end up at the same address.
+    let original = dylib_dep_path();
+    let copy = std::env::temp_dir().join(format!(
+        "backtrace-dlopen-{}-{}",
+        std::process::id(),
+        original.file_name().unwrap().to_str().unwrap()
+    ));
+    std::fs::copy(&original, &copy).unwrap();
+
+    let module_of_foo = |bt: &Backtrace| {
+        bt.frames()
+            .iter()
+            .find(|f| {
+                f.symbols()
+                    .iter()
+                    .any(|s| s.name().map(|n| n.to_string() == "foo").unwrap_or(false))
+            })
+            .and_then(|f| f.module())
+            .map(|m| m.path().to_owned())
+    };
+
+    let lib = unsafe { libloading::Library::new(&original).unwrap() };
+    let bt = backtrace_through_foo(&lib);
+    assert!(contains_foo(&bt));
+    assert_eq!(module_of_foo(&bt), Some(original.clone()));
+    drop(lib);
+
+    let lib = unsafe { libloading::Library::new(&copy).unwrap() };
+    let bt = backtrace_through_foo(&lib);
+    println!("{:?}", bt);
+    drop(lib);
+    std::fs::remove_file(&copy).unwrap();
+    assert!(contains_foo(&bt));
+    assert_eq!(module_of_foo(&bt), Some(copy));
+}
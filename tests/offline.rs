@@ -0,0 +1,163 @@
+// Tests for symbolicating backtraces against an explicit list of modules,
+// rather than against the libraries loaded into the current process.
+
+use backtrace::{modules, Backtrace, Module, ModuleSegment};
+use std::path::PathBuf;
+
+// Describes the current executable by looking for its mappings in
+// `/proc/self/maps`. This assumes a position independent executable whose
+// first segment is stated at address zero, which is what rustc produces by
+// default.
+#[cfg(target_os = "linux")]
+fn current_exe_module(build_id: Option<Vec<u8>>) -> Module {
+    let exe = std::env::current_exe().unwrap();
+    let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+    let mut start = usize::MAX;
+    let mut end = 0;
+    for line in maps.lines() {
+        let path = match line.split_whitespace().nth(5) {
+            Some(path) => PathBuf::from(path),
+            None => continue,
+        };
+        if path != exe {
+            continue;
+        }
+        let range = line.split_whitespace().next().unwrap();
+        let mut range = range.split('-');
+        let lo = usize::from_str_radix(range.next().unwrap(), 16).unwrap();
+        let hi = usize::from_str_radix(range.next().unwrap(), 16).unwrap();
+        start = start.min(lo);
+        end = end.max(hi);
+    }
+    assert!(start < end, "failed to find {:?} in /proc/self/maps", exe);
+    Module::new(
+        exe,
+        build_id,
+        start,
+        vec![ModuleSegment::new(0, end - start)],
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn contains_this_test(bt: &Backtrace) -> bool {
+    bt.frames().iter().flat_map(|f| f.symbols()).any(|s| {
+        s.name()
+            .map(|name| name.to_string().contains("resolves_against_modules"))
+            .unwrap_or(false)
+    })
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn resolves_against_modules() {
+    let mut bt = Backtrace::new_unresolved();
+    bt.resolve_with_modules(&[current_exe_module(None)]);
+    println!("{:?}", bt);
+    assert!(contains_this_test(&bt));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn unknown_addresses_are_unresolved() {
+    let mut bt = Backtrace::new_unresolved();
+    bt.resolve_with_modules(&[]);
+    assert!(bt.frames().iter().all(|f| f.symbols().is_empty()));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn mismatched_build_id_is_rejected() {
+    let mut bt = Backtrace::new_unresolved();
+    bt.resolve_with_modules(&[current_exe_module(Some(vec![0; 20]))]);
+    assert!(!contains_this_test(&bt));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn frames_record_their_module() {
+    let mut bt = Backtrace::new_unresolved();
+    let exe = std::env::current_exe().unwrap();
+    let index = bt
+        .frames()
+        .iter()
+        .position(|f| f.module().map(|m| m.path() == exe).unwrap_or(false))
+        .expect("no frame in the current executable");
+    let frame = &bt.frames()[index];
+    let module = frame.module().unwrap();
+    assert!(module.build_id().is_some());
+    assert_eq!(
+        frame.module_offset(),
+        Some(frame.ip() as usize - module.base_address() as usize)
+    );
+
+    // Resolving records the same module that was looked up beforehand.
+    bt.resolve();
+    assert_eq!(bt.frames()[index].module(), Some(module));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn relocates_frames_by_build_id() {
+    let mut bt = Backtrace::new_unresolved();
+    let build_id = bt
+        .frames()
+        .iter()
+        .filter_map(|f| f.module())
+        .find(|m| m.path() == std::env::current_exe().unwrap())
+        .and_then(|m| m.build_id().map(|id| id.to_vec()))
+        .expect("current executable has no build id");
+
+    // Pretend the executable was loaded somewhere else entirely; frames are
+    // matched up through their build ID and offset instead.
+    let real = current_exe_module(Some(build_id.clone()));
+    let moved = Module::new(
+        real.path().to_owned(),
+        Some(build_id),
+        real.bias() + 0x1000_0000,
+        real.segments().to_vec(),
+    );
+    bt.resolve_with_modules(&[moved]);
+    assert!(bt.frames().iter().flat_map(|f| f.symbols()).any(|s| {
+        s.name()
+            .map(|name| name.to_string().contains("relocates_frames_by_build_id"))
+            .unwrap_or(false)
+    }));
+}
+
+#[test]
+#[cfg(any(target_os = "linux", all(windows, target_env = "msvc")))]
+fn modules_include_current_exe() {
+    let exe = std::env::current_exe().unwrap();
+    let modules = modules();
+    let module = modules
+        .iter()
+        .find(|m| m.path() == exe)
+        .expect("current executable is not listed");
+    assert!(module.build_id().is_some());
+    assert!(!module.segments().is_empty());
+
+    let bt = Backtrace::new_unresolved();
+    let frame_module = bt
+        .frames()
+        .iter()
+        .filter_map(|f| f.module())
+        .find(|m| m.path() == exe)
+        .unwrap();
+    assert_eq!(frame_module.build_id(), module.build_id());
+    assert_eq!(frame_module.base_address() as usize, module.base_address());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn resolves_against_enumerated_modules() {
+    let mut bt = Backtrace::new_unresolved();
+    bt.resolve_with_modules(&modules());
+    assert!(bt.frames().iter().flat_map(|f| f.symbols()).any(|s| {
+        s.name()
+            .map(|name| {
+                name.to_string()
+                    .contains("resolves_against_enumerated_modules")
+            })
+            .unwrap_or(false)
+    }));
+}
@@ -0,0 +1,103 @@
+// Tests for capturing the backtraces of another process through `ptrace`. The
+// process is this test binary itself, re-run to only execute `child`.
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+))]
+mod linux {
+    use backtrace::{RemoteProcess, ThreadBacktrace};
+    use std::env;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::thread;
+    use std::time::Duration;
+
+    const CHILD: &str = "BACKTRACE_REMOTE_PROCESS_CHILD";
+
+    #[inline(never)]
+    fn wait_in_child() {
+        println!("ready");
+        loop {
+            thread::sleep(Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn child() {
+        if env::var_os(CHILD).is_some() {
+            wait_in_child();
+        }
+    }
+
+    // Spawns the child and waits for it to get to `wait_in_child`.
+    fn spawn() -> Child {
+        let mut child = Command::new(env::current_exe().unwrap())
+            .args(&["--exact", "linux::child", "--nocapture"])
+            .env(CHILD, "1")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        for line in stdout.lines() {
+            // The test harness has already printed the name of the test on
+            // the same line.
+            if line.unwrap().ends_with("ready") {
+                return child;
+            }
+        }
+        panic!("child exited early");
+    }
+
+    fn has_frame(thread: &ThreadBacktrace, suffix: &str) -> bool {
+        thread
+            .backtrace()
+            .frames()
+            .iter()
+            .flat_map(|f| f.symbols())
+            .filter_map(|s| s.name())
+            .any(|n| format!("{:#}", n).ends_with(suffix))
+    }
+
+    #[test]
+    fn backtraces_of_child() {
+        let mut child = spawn();
+        let threads = {
+            let process = RemoteProcess::attach(child.id()).unwrap();
+            assert_eq!(process.pid(), child.id());
+            assert!(process.threads().contains(&child.id()));
+
+            let exe = env::current_exe().unwrap();
+            assert!(process.modules().iter().any(|m| m.path() == exe));
+            process.backtraces()
+        };
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        for thread in &threads {
+            println!("{:?}", thread);
+        }
+        // Sleeping, the child has to be unwound from within libc back into the
+        // test. Its other threads may still be on their way out of spawning
+        // the test's thread, which can't be unwound from.
+        let waiting = threads
+            .iter()
+            .find(|t| has_frame(t, "linux::wait_in_child"))
+            .expect("no thread in `wait_in_child`");
+        assert!(has_frame(waiting, "linux::child"));
+    }
+
+    #[test]
+    fn resumes_after_detaching() {
+        let mut child = spawn();
+        drop(RemoteProcess::attach(child.id()).unwrap());
+        // A stopped child wouldn't get to exit from the signal.
+        child.kill().unwrap();
+        assert!(child.wait().is_ok());
+    }
+
+    #[test]
+    fn no_such_process() {
+        assert!(RemoteProcess::attach(i32::MAX as u32).is_err());
+    }
+}
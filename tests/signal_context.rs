@@ -0,0 +1,93 @@
+// Tests for capturing the backtrace of a context interrupted by a signal.
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+))]
+mod linux {
+    use backtrace::Backtrace;
+    use std::os::unix::thread::JoinHandleExt;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+    use std::{mem, ptr, thread};
+
+    static SPINNING: AtomicBool = AtomicBool::new(false);
+    static STOP: AtomicBool = AtomicBool::new(false);
+    static CAPTURED: Mutex<Option<Backtrace>> = Mutex::new(None);
+
+    #[inline(never)]
+    fn spin() {
+        SPINNING.store(true, Ordering::SeqCst);
+        while !STOP.load(Ordering::SeqCst) {}
+    }
+
+    extern "C" fn handler(_: libc::c_int, _: *mut libc::siginfo_t, context: *mut libc::c_void) {
+        let bt = unsafe { Backtrace::from_context(context) };
+        *CAPTURED.lock().unwrap() = Some(bt);
+        STOP.store(true, Ordering::SeqCst);
+    }
+
+    fn names(bt: &Backtrace) -> Vec<String> {
+        bt.frames()
+            .iter()
+            .map(|f| {
+                f.symbols()
+                    .last()
+                    .and_then(|s| s.name())
+                    .map(|n| format!("{:#}", n))
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn starts_at_interrupted_frame() {
+        unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = handler as usize;
+            action.sa_flags = libc::SA_SIGINFO;
+            assert_eq!(libc::sigaction(libc::SIGUSR1, &action, ptr::null_mut()), 0);
+        }
+
+        // Interrupt a thread busy in `spin`, so the interrupted frame is known.
+        let spinner = thread::spawn(spin);
+        while !SPINNING.load(Ordering::SeqCst) {}
+        while CAPTURED.lock().unwrap().is_none() {
+            unsafe {
+                libc::pthread_kill(spinner.as_pthread_t(), libc::SIGUSR1);
+            }
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        spinner.join().unwrap();
+
+        let mut bt = CAPTURED.lock().unwrap().take().unwrap();
+        bt.resolve();
+        println!("{:?}", bt);
+        let names = names(&bt);
+
+        // `spin` may have been interrupted in a call to the atomics, but nothing
+        // from the signal handler comes before it.
+        let spin = names
+            .iter()
+            .position(|n| n.ends_with("linux::spin"))
+            .expect("no frame for `spin`");
+        assert!(spin < 4, "`spin` is frame {}", spin);
+        assert!(!names.iter().any(|n| n.contains("handler")));
+        assert!(!names.iter().any(|n| n.contains("restore_rt")));
+
+        // The interrupted frame was executing its ip rather than returning to
+        // it, unlike all of its callers.
+        let frames = bt.frames();
+        assert!(!frames[0].is_return_address());
+        assert!(frames[1..].iter().all(|f| f.is_return_address()));
+
+        // Modules aren't looked up in the handler, but are once resolved.
+        assert!(frames[spin].module().is_some());
+
+        // The CFA the stack was walked with is kept, unless the frames of
+        // the `frame-pointers` unwinder, which doesn't know it, are used.
+        if !cfg!(feature = "frame-pointers") {
+            assert!(frames[spin].cfa().is_some());
+        }
+    }
+}
@@ -0,0 +1,169 @@
+// Tests for capturing backtraces from within signal handlers through
+// `trace_into`, the way a sampling profiler would.
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use backtrace::RawFrame;
+    use std::cell::UnsafeCell;
+    use std::mem::{self, MaybeUninit};
+    use std::ptr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    const SAMPLES: usize = 16;
+    const DEPTH: usize = 64;
+
+    struct Buffers(UnsafeCell<[[MaybeUninit<RawFrame>; DEPTH]; SAMPLES]>);
+
+    // Each buffer is only written by the one signal handler that claimed it
+    // through `TAKEN`, and only read after the timer has been disarmed.
+    unsafe impl Sync for Buffers {}
+
+    static BUFFERS: Buffers = Buffers(UnsafeCell::new([[MaybeUninit::uninit(); DEPTH]; SAMPLES]));
+    static TAKEN: AtomicUsize = AtomicUsize::new(0);
+    static DONE: AtomicUsize = AtomicUsize::new(0);
+    #[allow(clippy::declare_interior_mutable_const)]
+    const ZERO: AtomicUsize = AtomicUsize::new(0);
+    static LENS: [AtomicUsize; SAMPLES] = [ZERO; SAMPLES];
+
+    extern "C" fn on_sigprof(_: libc::c_int) {
+        let i = TAKEN.fetch_add(1, Ordering::SeqCst);
+        if i >= SAMPLES {
+            return;
+        }
+        let buffer = unsafe { &mut (*BUFFERS.0.get())[i] };
+        LENS[i].store(backtrace::trace_into(buffer), Ordering::SeqCst);
+        DONE.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[inline(never)]
+    fn allocate_until_sampled() {
+        let start = Instant::now();
+        let mut keep = Vec::new();
+        while DONE.load(Ordering::SeqCst) < SAMPLES && start.elapsed() < Duration::from_secs(10) {
+            keep.push(vec![0u8; 64]);
+            if keep.len() > 1000 {
+                keep.clear();
+            }
+        }
+    }
+
+    fn set_timer(interval: libc::suseconds_t) {
+        let value = libc::timeval {
+            tv_sec: 0,
+            tv_usec: interval,
+        };
+        let timer = libc::itimerval {
+            it_interval: value,
+            it_value: value,
+        };
+        unsafe {
+            assert_eq!(
+                libc::setitimer(libc::ITIMER_PROF, &timer, ptr::null_mut()),
+                0
+            );
+        }
+    }
+
+    fn names(ips: &[*mut libc::c_void]) -> Vec<String> {
+        let mut names = Vec::new();
+        for &ip in ips {
+            backtrace::resolve(ip, |symbol| {
+                if let Some(name) = symbol.name() {
+                    names.push(format!("{:#}", name));
+                }
+            });
+        }
+        names
+    }
+
+    // The frame-pointer unwinder needs to be told where the stack is without
+    // asking pthreads, which isn't async-signal-safe.
+    #[cfg(feature = "frame-pointers")]
+    fn install_stack_limits_hook() {
+        static START: AtomicUsize = AtomicUsize::new(0);
+        static END: AtomicUsize = AtomicUsize::new(0);
+
+        fn hook() -> Option<std::ops::Range<usize>> {
+            let local = 0;
+            let here = &local as *const i32 as usize;
+            let limits = START.load(Ordering::SeqCst)..END.load(Ordering::SeqCst);
+            if limits.contains(&here) {
+                Some(limits)
+            } else {
+                Some(0..0)
+            }
+        }
+
+        unsafe {
+            let mut attr: libc::pthread_attr_t = mem::zeroed();
+            assert_eq!(libc::pthread_getattr_np(libc::pthread_self(), &mut attr), 0);
+            let mut addr = ptr::null_mut();
+            let mut size = 0;
+            assert_eq!(libc::pthread_attr_getstack(&attr, &mut addr, &mut size), 0);
+            libc::pthread_attr_destroy(&mut attr);
+            START.store(addr as usize, Ordering::SeqCst);
+            END.store(addr as usize + size, Ordering::SeqCst);
+        }
+        backtrace::set_stack_limits_hook(Some(hook));
+    }
+
+    #[cfg(not(feature = "frame-pointers"))]
+    fn install_stack_limits_hook() {}
+
+    #[test]
+    fn sample_while_allocating() {
+        install_stack_limits_hook();
+
+        // Let the unwinder initialize whatever it lazily initializes outside
+        // of the signal handler.
+        let mut warm = [MaybeUninit::uninit(); DEPTH];
+        assert!(backtrace::trace_into(&mut warm) > 0);
+
+        unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = on_sigprof as usize;
+            action.sa_flags = libc::SA_RESTART;
+            assert_eq!(libc::sigaction(libc::SIGPROF, &action, ptr::null_mut()), 0);
+        }
+        set_timer(1000);
+        allocate_until_sampled();
+        set_timer(0);
+        unsafe {
+            libc::signal(libc::SIGPROF, libc::SIG_IGN);
+        }
+
+        let done = DONE.load(Ordering::SeqCst);
+        assert_eq!(done, SAMPLES);
+        let mut saw_allocator = false;
+        for (i, len) in LENS.iter().enumerate() {
+            let len = len.load(Ordering::SeqCst);
+            assert!(len <= DEPTH);
+            // Only the frame-pointer unwinder, which only knows the limits of
+            // this thread's stack, comes up empty when a signal is delivered to
+            // another thread.
+            if len == 0 && cfg!(feature = "frame-pointers") {
+                continue;
+            }
+            assert!(len > 0);
+            let frames = unsafe { &(&*BUFFERS.0.get())[i][..len] };
+            let ips = frames
+                .iter()
+                .map(|frame| unsafe { frame.assume_init() }.ip())
+                .collect::<Vec<_>>();
+            let names = names(&ips);
+            assert!(
+                names.iter().any(|n| n.ends_with("on_sigprof")),
+                "{:?}",
+                names
+            );
+            saw_allocator |= names.iter().any(|n| n.ends_with("allocate_until_sampled"));
+        }
+        assert!(saw_allocator);
+    }
+
+    #[test]
+    fn empty_buffer() {
+        assert_eq!(backtrace::trace_into(&mut []), 0);
+    }
+}
@@ -9,6 +9,9 @@ const ENABLED: bool = cfg!(all(
     target_os = "linux",
     // On ARM finding the enclosing function is simply returning the ip itself.
     not(target_arch = "arm"),
+    // Frame records don't say which function they belong to, so the
+    // frame-pointer unwinder returns the ip itself as well.
+    not(feature = "frame-pointers"),
 ));
 
 #[test]
@@ -42,3 +45,34 @@ fn backtrace_new_should_start_with_call_site_trace() {
     let frame_ip = b.frames().first().unwrap().symbol_address() as usize;
     assert_eq!(this_ip, frame_ip);
 }
+
+#[test]
+fn backtrace_builder_should_start_with_call_site_trace() {
+    if !ENABLED {
+        return;
+    }
+    let b = Backtrace::builder().capture();
+    println!("{:?}", b);
+
+    assert!(!b.frames().is_empty());
+
+    let this_ip = backtrace_builder_should_start_with_call_site_trace as usize;
+    let frame_ip = b.frames().first().unwrap().symbol_address() as usize;
+    assert_eq!(this_ip, frame_ip);
+    assert!(!b.frames()[0].symbols().is_empty());
+}
+
+#[test]
+fn backtrace_builder_should_skip_until_frame() {
+    if !ENABLED {
+        return;
+    }
+    let full = Backtrace::builder().resolve(false).capture();
+    let this_ip = backtrace_builder_should_skip_until_frame as usize;
+    let b = Backtrace::builder()
+        .skip_until(this_ip as *mut _)
+        .max_frames(1)
+        .resolve(false)
+        .capture();
+    assert_eq!(b.frames()[0].ip(), full.frames()[1].ip());
+}
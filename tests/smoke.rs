@@ -321,3 +321,185 @@ fn sp_smoke_test() {
         }
     }
 }
+
+#[test]
+fn cfa_and_registers_smoke_test() {
+    let mut live = Vec::new();
+    let mut cloned = Vec::new();
+    backtrace::trace(|frame| {
+        let registers = (0..64).map(|n| frame.register(n)).collect::<Vec<_>>();
+        live.push((frame.sp() as usize, frame.cfa(), registers));
+        cloned.push(frame.clone());
+        true
+    });
+
+    // Not every backend knows these, but libgcc and `gimli-unwind` do.
+    if live[0].1.is_none() {
+        assert!(!cfg!(all(
+            target_env = "gnu",
+            target_arch = "x86_64",
+            not(feature = "frame-pointers"),
+            not(miri),
+        )));
+        return;
+    }
+    for ((sp, cfa, registers), frame) in live.iter().zip(&cloned) {
+        let cfa = cfa.unwrap() as usize;
+        if *sp != 0 {
+            assert!(cfa >= *sp);
+        }
+        // Only registers preserved across calls are reported.
+        if cfg!(target_arch = "x86_64") {
+            assert!(registers[3].is_some());
+            assert!(registers[0].is_none());
+        }
+
+        // Clones may leave the registers out, as reading them isn't free.
+        let captured = backtrace::BacktraceFrame::from(frame.clone());
+        assert_eq!(frame.cfa(), Some(cfa as *mut _));
+        assert_eq!(captured.cfa(), Some(cfa as *mut _));
+        for (n, value) in registers.iter().enumerate() {
+            let cloned = frame.register(n as u16);
+            assert!(cloned.is_none() || cloned == *value);
+            assert_eq!(captured.register(n as u16), cloned);
+        }
+    }
+
+    // The frames of a `Backtrace` keep them.
+    let bt = backtrace::Backtrace::new_unresolved();
+    let frame = &bt.frames()[0];
+    assert!(frame.cfa().is_some());
+    if cfg!(target_arch = "x86_64") {
+        assert!(frame.register(3).is_some());
+    }
+}
+
+#[test]
+fn inlined_symbols_smoke_test() {
+    inlined_into_caller();
+
+    #[inline(always)]
+    fn inlined_into_caller() {
+        capture_caller()
+    }
+
+    #[inline(never)]
+    fn capture_caller() {
+        let bt = backtrace::Backtrace::new();
+        let symbols = bt.frames()[1].symbols();
+        println!("{:?}", symbols);
+
+        // Inlining is only known with gimli and debuginfo.
+        if !symbols.iter().any(|s| s.is_inlined()) {
+            return;
+        }
+        let (last, inlined) = symbols.split_last().unwrap();
+        assert!(!last.is_inlined());
+        assert!(last.call_lineno().is_none());
+        assert!(inlined.iter().all(|s| s.is_inlined()));
+        // The call site of the innermost inlined function is where the
+        // function it was inlined into is at.
+        let inlined = inlined.last().unwrap();
+        assert!(format!("{:#}", inlined.name().unwrap()).ends_with("inlined_into_caller"));
+        assert!(last.lineno().is_some());
+        assert_eq!(inlined.call_lineno(), last.lineno());
+        assert_eq!(inlined.call_colno(), last.colno());
+        assert_eq!(inlined.call_filename(), last.filename());
+
+        let printed = format!("{:?}", Printed(&bt, backtrace::PrintFmt::Short, true));
+        assert!(printed.contains("inlined_into_caller (inlined)\n"));
+        assert!(!format!("{:?}", bt).contains("(inlined)"));
+    }
+}
+
+#[test]
+fn function_offsets_smoke_test() {
+    let bt = backtrace::Backtrace::new();
+    let frame = &bt.frames()[0];
+    let symbol = frame.symbols().last().unwrap();
+    println!("{:?}", symbol);
+
+    // Not every backend knows where functions start.
+    let offset = match symbol.offset() {
+        Some(offset) => offset,
+        None => return,
+    };
+    let addr = symbol.addr().unwrap() as usize;
+    assert_eq!(addr + offset, frame.ip() as usize);
+    if let Some(size) = symbol.size() {
+        assert!(offset < size);
+    }
+    let name = format!("{}", symbol.name().unwrap());
+    if name.contains("function_offsets_smoke_test") {
+        assert_eq!(addr, function_offsets_smoke_test as *const () as usize);
+    }
+
+    // Resolving the ip as a bare address measures from it too.
+    let mut offsets = Vec::new();
+    backtrace::resolve(frame.ip(), |symbol| offsets.push(symbol.offset()));
+    assert_eq!(offsets.last(), Some(&Some(offset)));
+
+    // The printed offset is that of the ip itself.
+    let printed = format!(
+        "{:?}",
+        Printed(&bt, backtrace::PrintFmt::FullWithOffsets, false)
+    );
+    assert!(printed.contains(&format!("{}+{:#x}\n", name, offset)));
+    assert!(!format!("{:#?}", bt).contains(&format!("{}+", name)));
+}
+
+#[test]
+fn resolve_batch_smoke_test() {
+    // Resolve every frame several times over, interleaved, so that the
+    // addresses of different libraries are mixed up.
+    let mut ips = Vec::new();
+    backtrace::trace(|frame| {
+        ips.push(frame.ip());
+        true
+    });
+    let ips = ips
+        .iter()
+        .chain(ips.iter().rev())
+        .chain(&ips)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let describe = |symbol: &backtrace::Symbol| {
+        (
+            symbol.name().map(|name| name.to_string()),
+            symbol.addr(),
+            symbol.filename().map(|path| path.to_path_buf()),
+            symbol.lineno(),
+        )
+    };
+    let expected = ips
+        .iter()
+        .map(|&ip| {
+            let mut symbols = Vec::new();
+            backtrace::resolve(ip, |symbol| symbols.push(describe(symbol)));
+            symbols
+        })
+        .collect::<Vec<_>>();
+    let mut actual = vec![Vec::new(); ips.len()];
+    backtrace::resolve_batch(&ips, |i, symbol| actual[i].push(describe(symbol)));
+
+    assert_eq!(actual, expected);
+    assert!(actual.iter().any(|symbols| !symbols.is_empty()));
+}
+
+// Prints a backtrace through `BacktraceFmt` with the options given.
+struct Printed<'a>(&'a backtrace::Backtrace, backtrace::PrintFmt, bool);
+
+impl std::fmt::Debug for Printed<'_> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut print_path = |fmt: &mut std::fmt::Formatter<'_>,
+                              path: backtrace::BytesOrWideString<'_>| {
+            std::fmt::Display::fmt(&path, fmt)
+        };
+        let mut f = backtrace::BacktraceFmt::new(fmt, self.1, &mut print_path);
+        for frame in self.0.frames() {
+            f.frame().label_inlined(self.2).backtrace_frame(frame)?;
+        }
+        f.finish()
+    }
+}
@@ -0,0 +1,62 @@
+// Tests for configuring the cache of parsed debuginfo. These all live in one
+// test since the cache and its statistics are global to the process.
+
+use backtrace::{Backtrace, SymbolCacheCapacity};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn capacity_and_stats() {
+    // A backtrace from here crosses at least the test executable and libc.
+    let bt = Backtrace::new_unresolved();
+    let resolve = || {
+        let mut bt = bt.clone();
+        bt.resolve();
+    };
+
+    // With room for a single image, resolving the backtrace has to keep on
+    // swapping debuginfo in and out.
+    backtrace::set_symbol_cache_capacity(SymbolCacheCapacity::Entries(1));
+    let before = backtrace::symbol_cache_stats();
+    resolve();
+    let after = backtrace::symbol_cache_stats();
+    assert!(after.misses() >= before.misses() + 2);
+    assert!(after.evictions() > before.evictions());
+    assert_eq!(after.entries(), 1);
+    assert!(after.bytes() > 0);
+
+    // With enough room, resolving it again hits the cache every time.
+    backtrace::set_symbol_cache_capacity(SymbolCacheCapacity::Entries(16));
+    resolve();
+    let before = backtrace::symbol_cache_stats();
+    resolve();
+    let after = backtrace::symbol_cache_stats();
+    assert!(after.entries() >= 2);
+    assert!(after.hits() > before.hits());
+    assert_eq!(after.misses(), before.misses());
+    assert_eq!(after.evictions(), before.evictions());
+
+    // Lowering the capacity evicts right away.
+    backtrace::set_symbol_cache_capacity(SymbolCacheCapacity::Bytes(0));
+    let stats = backtrace::symbol_cache_stats();
+    assert_eq!(stats.entries(), 0);
+    assert_eq!(stats.bytes(), 0);
+    assert!(stats.evictions() >= after.evictions() + 2);
+
+    // A byte budget still lets in the debuginfo being used.
+    resolve();
+    assert_eq!(backtrace::symbol_cache_stats().entries(), 1);
+
+    // Debuginfo of modules given explicitly is cached across calls too.
+    backtrace::set_symbol_cache_capacity(SymbolCacheCapacity::Entries(16));
+    let modules = backtrace::modules();
+    let resolve_with_modules = || {
+        let mut bt = bt.clone();
+        bt.resolve_with_modules(&modules);
+    };
+    resolve_with_modules();
+    let before = backtrace::symbol_cache_stats();
+    resolve_with_modules();
+    let after = backtrace::symbol_cache_stats();
+    assert!(after.hits() >= before.hits() + 2);
+    assert_eq!(after.misses(), before.misses());
+}
@@ -0,0 +1,94 @@
+// Tests for symbolizing addresses within an object file on disk, through the
+// current executable.
+
+use backtrace::{Backtrace, Symbolizer};
+
+// Translates an address in this process into an address stated in the current
+// executable, returning `None` if the address isn't within the executable.
+#[cfg(target_os = "linux")]
+fn exe_address(ip: *mut std::ffi::c_void) -> Option<u64> {
+    let exe = std::env::current_exe().unwrap();
+    let module = backtrace::modules()
+        .into_iter()
+        .find(|m| m.path() == exe)
+        .unwrap();
+    let addr = (ip as usize).wrapping_sub(module.bias());
+    let within = module.segments().iter().any(|s| {
+        let start = s.stated_virtual_memory_address();
+        start <= addr && addr < start + s.size()
+    });
+    if within {
+        Some(addr as u64)
+    } else {
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn names(symbolizer: &mut Symbolizer, addr: u64) -> Vec<String> {
+    let mut names = Vec::new();
+    symbolizer.resolve(addr, |sym| {
+        names.push(sym.name().map(|n| n.to_string()).unwrap_or_default());
+    });
+    names
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn resolves_functions_in_file() {
+    let exe = std::env::current_exe().unwrap();
+    let mut symbolizer = Symbolizer::new(&exe).unwrap();
+    assert!(symbolizer.build_id().is_some());
+
+    let addr = exe_address(resolves_functions_in_file as *mut _).unwrap();
+    let names = names(&mut symbolizer, addr);
+    assert!(
+        names
+            .iter()
+            .any(|n| n.contains("resolves_functions_in_file")),
+        "{:?}",
+        names
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn yields_inlined_frames() {
+    #[inline(never)]
+    fn outer() -> Backtrace {
+        inner()
+    }
+
+    #[inline(always)]
+    fn inner() -> Backtrace {
+        Backtrace::new_unresolved()
+    }
+
+    let bt = outer();
+    let exe = std::env::current_exe().unwrap();
+    let mut symbolizer = Symbolizer::new(&exe).unwrap();
+    let found = bt.frames().iter().any(|frame| {
+        let addr = match exe_address(frame.ip()) {
+            // Look up the call instruction rather than the return address.
+            Some(addr) => addr - 1,
+            None => return false,
+        };
+        let names = names(&mut symbolizer, addr);
+        let inner = names
+            .iter()
+            .position(|n| n.contains("yields_inlined_frames::inner"));
+        let outer = names
+            .iter()
+            .position(|n| n.contains("yields_inlined_frames::outer"));
+        match (inner, outer) {
+            (Some(inner), Some(outer)) => inner < outer,
+            _ => false,
+        }
+    });
+    assert!(found);
+}
+
+#[test]
+fn missing_file_is_rejected() {
+    assert!(Symbolizer::new("/this/file/does/not/exist").is_none());
+}
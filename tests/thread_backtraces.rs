@@ -0,0 +1,107 @@
+// Tests for capturing the backtraces of other threads of the process.
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+))]
+mod linux {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::{channel, Receiver};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[inline(never)]
+    fn wait_in_worker(rx: Receiver<()>) {
+        rx.recv().unwrap();
+    }
+
+    fn has_frame(thread: &backtrace::ThreadBacktrace, suffix: &str) -> bool {
+        thread
+            .backtrace()
+            .frames()
+            .iter()
+            .flat_map(|f| f.symbols())
+            .filter_map(|s| s.name())
+            .any(|n| format!("{:#}", n).ends_with(suffix))
+    }
+
+    #[test]
+    fn all_threads() {
+        let (tx, rx) = channel();
+        let (ready_tx, ready_rx) = channel();
+        let worker = thread::Builder::new()
+            .name("bt-worker".to_string())
+            .spawn(move || {
+                ready_tx.send(()).unwrap();
+                wait_in_worker(rx);
+            })
+            .unwrap();
+        ready_rx.recv().unwrap();
+
+        let threads = backtrace::thread_backtraces();
+        for thread in &threads {
+            println!("{:?}", thread);
+        }
+        tx.send(()).unwrap();
+        worker.join().unwrap();
+
+        // Blocked in a syscall, the worker has to be unwound from within libc
+        // back into this test.
+        let worker = threads
+            .iter()
+            .find(|t| t.name() == Some("bt-worker"))
+            .expect("no backtrace for the worker");
+        assert!(has_frame(worker, "linux::wait_in_worker"));
+
+        let current = threads
+            .iter()
+            .find(|t| has_frame(t, "linux::all_threads"))
+            .expect("no backtrace for the current thread");
+        assert_ne!(current.id(), worker.id());
+    }
+
+    #[test]
+    fn unresponsive_thread() {
+        // A thread blocking the signal never responds, which mustn't keep
+        // other threads from using the crate in the meantime.
+        let (tx, rx) = channel();
+        let (id_tx, id_rx) = channel();
+        let blocked = thread::spawn(move || {
+            unsafe {
+                let mut set = std::mem::zeroed();
+                libc::sigemptyset(&mut set);
+                libc::sigaddset(&mut set, libc::SIGRTMAX());
+                libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut());
+            }
+            id_tx
+                .send(unsafe { libc::syscall(libc::SYS_gettid) as u32 })
+                .unwrap();
+            wait_in_worker(rx);
+        });
+        let id = id_rx.recv().unwrap();
+
+        let done = Arc::new(AtomicBool::new(false));
+        let requester = {
+            let done = done.clone();
+            thread::spawn(move || {
+                let backtrace = backtrace::thread_backtrace(id);
+                done.store(true, Ordering::SeqCst);
+                backtrace
+            })
+        };
+        thread::sleep(Duration::from_millis(100));
+        let bt = backtrace::Backtrace::new();
+        assert!(!done.load(Ordering::SeqCst));
+        assert!(!bt.frames().is_empty());
+
+        assert!(requester.join().unwrap().is_none());
+        tx.send(()).unwrap();
+        blocked.join().unwrap();
+    }
+
+    #[test]
+    fn unknown_thread() {
+        assert!(backtrace::thread_backtrace(u32::max_value()).is_none());
+    }
+}